@@ -0,0 +1,168 @@
+use crate::Result;
+use git2::{Commit, Oid, Repository};
+use std::collections::{HashMap, VecDeque};
+
+/// Works out on which branch each commit reachable from `tips` was made.
+///
+/// Every tip claims the commits on its first-parent chain, stopping at the
+/// first commit that is already claimed. The mainline branch goes first, so a
+/// feature branch only claims the commits it added on top of it. For each
+/// claimed merge commit, the chains behind its other parents are claimed for
+/// the branch named in the merge message, which recovers topic branches that
+/// have been merged and deleted. Merges without a recognizable message credit
+/// the branch that did the merge.
+///
+/// Chains are only followed back to `since`, so older commits are left
+/// unattributed.
+pub fn attribute_branches(
+    repo: &Repository,
+    tips: &[(String, Oid)],
+    since: i64,
+) -> Result<HashMap<Oid, String>> {
+    let mainline = mainline_branch(repo, tips);
+    let mut tips = tips.iter().collect::<Vec<_>>();
+    tips.sort_by_key(|(name, _)| (Some(name) != mainline.as_ref(), name.clone()));
+
+    let mut branches = HashMap::new();
+    let mut merges = VecDeque::new();
+    for (name, oid) in tips {
+        claim_chain(repo, *oid, name, since, &mut branches, &mut merges)?;
+    }
+    while let Some((merge, into)) = merges.pop_front() {
+        let merge = repo.find_commit(merge)?;
+        let name = merged_branch(&merge).unwrap_or(into);
+        for parent in merge.parent_ids().skip(1) {
+            claim_chain(repo, parent, &name, since, &mut branches, &mut merges)?;
+        }
+    }
+    Ok(branches)
+}
+
+/// Claims the first-parent chain starting at `oid` for `name`.
+fn claim_chain(
+    repo: &Repository,
+    oid: Oid,
+    name: &str,
+    since: i64,
+    branches: &mut HashMap<Oid, String>,
+    merges: &mut VecDeque<(Oid, String)>,
+) -> Result<()> {
+    let mut next = Some(oid);
+    while let Some(oid) = next {
+        if branches.contains_key(&oid) {
+            break;
+        }
+        let commit = repo.find_commit(oid)?;
+        if commit.time().seconds() < since {
+            break;
+        }
+        branches.insert(oid, name.to_string());
+        if commit.parent_count() > 1 {
+            merges.push_back((oid, name.to_string()));
+        }
+        next = commit.parent_id(0).ok();
+    }
+    Ok(())
+}
+
+/// The branch that everything else is merged into: `main` or `master`, locally
+/// or on `origin`, and otherwise the checked out branch if it is one of the tips.
+fn mainline_branch(repo: &Repository, tips: &[(String, Oid)]) -> Option<String> {
+    let head = repo
+        .head()
+        .ok()
+        .filter(|head| head.is_branch())
+        .and_then(|head| head.shorthand().map(|name| name.to_string()));
+    ["main", "master", "origin/main", "origin/master"]
+        .map(String::from)
+        .into_iter()
+        .chain(head)
+        .find(|candidate| tips.iter().any(|(name, _)| name == candidate))
+}
+
+/// Extracts the name of the merged branch from the default merge messages of
+/// git, GitHub, GitLab and Bitbucket.
+fn merged_branch(merge: &Commit) -> Option<String> {
+    let summary = merge.summary()?;
+    if let Some(rest) = summary
+        .strip_prefix("Merge branch '")
+        .or_else(|| summary.strip_prefix("Merge remote-tracking branch '"))
+    {
+        // Merge branch 'topic' into main
+        let (name, _) = rest.split_once('\'')?;
+        return Some(name.to_string());
+    }
+    if let Some(rest) = summary.strip_prefix("Merge pull request ") {
+        // Merge pull request #12 from owner/topic
+        let (_, source) = rest.split_once(" from ")?;
+        let source = source.split_whitespace().next()?;
+        let name = source.split_once('/').map_or(source, |(_, name)| name);
+        return Some(name.to_string());
+    }
+    if let Some(rest) = summary.strip_prefix("Merged in ") {
+        // Merged in topic (pull request #12)
+        let name = rest.split_whitespace().next()?;
+        return Some(name.to_string());
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::TestRepo;
+
+    fn tips(tips: &[(&str, Oid)]) -> Vec<(String, Oid)> {
+        tips.iter()
+            .map(|(name, oid)| (name.to_string(), *oid))
+            .collect()
+    }
+
+    #[test]
+    fn credits_merged_and_deleted_topic() {
+        let mut test = TestRepo::new("merged-topic");
+        let base = test.commit("base", &[]);
+        let topic1 = test.commit("topic 1", &[base]);
+        let topic2 = test.commit("topic 2", &[topic1]);
+        let fix = test.commit("fix on main", &[base]);
+        let merge = test.commit("Merge branch 'topic'", &[fix, topic2]);
+
+        let branches = attribute_branches(&test.repo, &tips(&[("main", merge)]), 0).unwrap();
+
+        assert_eq!(branches[&base], "main");
+        assert_eq!(branches[&fix], "main");
+        assert_eq!(branches[&merge], "main");
+        assert_eq!(branches[&topic1], "topic");
+        assert_eq!(branches[&topic2], "topic");
+    }
+
+    #[test]
+    fn mainline_wins_over_feature_branches() {
+        let mut test = TestRepo::new("mainline");
+        let first = test.commit("first", &[]);
+        let second = test.commit("second", &[first]);
+        let feature = test.commit("feature", &[second]);
+        let third = test.commit("third", &[second]);
+        let tips = tips(&[("a-feature", feature), ("main", third)]);
+
+        let branches = attribute_branches(&test.repo, &tips, 0).unwrap();
+
+        assert_eq!(branches[&first], "main");
+        assert_eq!(branches[&second], "main");
+        assert_eq!(branches[&third], "main");
+        assert_eq!(branches[&feature], "a-feature");
+    }
+
+    #[test]
+    fn stops_at_since() {
+        let mut test = TestRepo::new("since");
+        let old = test.commit("old", &[]);
+        let new = test.commit("new", &[old]);
+        let since = test.repo.find_commit(new).unwrap().time().seconds();
+
+        let branches = attribute_branches(&test.repo, &tips(&[("main", new)]), since).unwrap();
+
+        assert_eq!(branches.get(&old), None);
+        assert_eq!(branches[&new], "main");
+    }
+}
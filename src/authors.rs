@@ -0,0 +1,264 @@
+use crate::{Error, Result};
+use git2::{Commit, Mailmap, Repository, Signature};
+use regex::{Regex, RegexBuilder};
+
+/// Decides whose commits get listed.
+///
+/// Patterns match case-insensitively, anywhere in the text. A commit is
+/// listed if it matches one of the included authors, or if none are given,
+/// and doesn't match any of the excluded ones.
+#[derive(Clone, Debug, Default)]
+pub struct AuthorFilter {
+    /// Matched against `Name <email>`
+    authors: Vec<Regex>,
+    /// Matched against just the email
+    emails: Vec<Regex>,
+    /// Matched against `Name <email>`
+    excluded: Vec<Regex>,
+    /// Whether to include the identity configured in each repository
+    me: bool,
+    /// The configured `user.name` and `user.email`, once resolved
+    identity: Option<(Option<String>, Option<String>)>,
+}
+
+impl AuthorFilter {
+    /// Builds a filter from patterns, which are regular expressions if `regex`
+    /// is set and literal text otherwise. With `me`, commits by the identity
+    /// from `user.name` and `user.email` are included too, see [AuthorFilter::resolve].
+    pub fn new(
+        authors: &[String],
+        emails: &[String],
+        excluded: &[String],
+        regex: bool,
+        me: bool,
+    ) -> Result<Self> {
+        let compile = |patterns: &[String]| {
+            patterns
+                .iter()
+                .map(|pattern| {
+                    let pattern = if regex {
+                        pattern.clone()
+                    } else {
+                        regex::escape(pattern)
+                    };
+                    RegexBuilder::new(&pattern).case_insensitive(true).build()
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+        Ok(Self {
+            authors: compile(authors)?,
+            emails: compile(emails)?,
+            excluded: compile(excluded)?,
+            me,
+            identity: None,
+        })
+    }
+
+    /// The filter to use for `repo`, with the identity from its git config if
+    /// the filter includes it.
+    pub fn resolve(&self, repo: &Repository) -> Result<Self> {
+        let mut filter = self.clone();
+        if self.me {
+            let config = repo.config()?;
+            let name = config.get_string("user.name").ok();
+            let email = config.get_string("user.email").ok();
+            if name.is_none() && email.is_none() {
+                let path = repo.workdir().unwrap_or(repo.path());
+                return Err(Error::MissingIdentity {
+                    path: path.display().to_string(),
+                });
+            }
+            filter.identity = Some((name, email));
+        }
+        Ok(filter)
+    }
+
+    pub fn matches(&self, author: &Signature) -> bool {
+        let name = author.name().unwrap_or_default();
+        let email = author.email().unwrap_or_default();
+        let full = author.to_string();
+        let is_me = self.identity.as_ref().is_some_and(|(me_name, me_email)| {
+            me_name
+                .as_ref()
+                .is_some_and(|me| me.eq_ignore_ascii_case(name))
+                || me_email
+                    .as_ref()
+                    .is_some_and(|me| me.eq_ignore_ascii_case(email))
+        });
+        let included = if self.authors.is_empty() && self.emails.is_empty() && !self.me {
+            true
+        } else {
+            is_me
+                || self.authors.iter().any(|author| author.is_match(&full))
+                || self.emails.iter().any(|pattern| pattern.is_match(email))
+        };
+        included && !self.excluded.iter().any(|author| author.is_match(&full))
+    }
+}
+
+/// Maps the authors of commits to their canonical identities.
+///
+/// The repository's own mailmap, from `.mailmap`, `mailmap.file` and
+/// `mailmap.blob`, applies first. A shared mailmap then applies on top, so one
+/// file can unify identities across all repositories.
+pub struct Identities {
+    repo: Mailmap,
+    shared: Option<Mailmap>,
+}
+
+impl Identities {
+    /// Loads the mailmap of `repo`, and parses `shared` in mailmap format.
+    pub fn new(repo: &Repository, shared: Option<&str>) -> Result<Self> {
+        Ok(Self {
+            repo: repo.mailmap()?,
+            shared: shared.map(Mailmap::from_buffer).transpose()?,
+        })
+    }
+
+    pub fn author(&self, commit: &Commit) -> Result<Signature<'static>> {
+        let author = commit.author_with_mailmap(&self.repo)?;
+        Ok(match &self.shared {
+            Some(shared) => shared.resolve_signature(&author)?,
+            None => author,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::TestRepo;
+
+    fn strings(patterns: &[&str]) -> Vec<String> {
+        patterns.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn filter(authors: &[&str], emails: &[&str], excluded: &[&str], regex: bool) -> AuthorFilter {
+        let (authors, emails, excluded) = (strings(authors), strings(emails), strings(excluded));
+        AuthorFilter::new(&authors, &emails, &excluded, regex, false).unwrap()
+    }
+
+    /// Which of Alice, Bob and Carol the filter lets through.
+    fn matching(filter: &AuthorFilter) -> Vec<&'static str> {
+        [
+            ("Alice Smith", "alice@example.com"),
+            ("Bob", "bob@example.org"),
+            ("Carol", "carol@example.com"),
+        ]
+        .into_iter()
+        .filter(|(name, email)| filter.matches(&Signature::now(name, email).unwrap()))
+        .map(|(name, _)| name)
+        .collect()
+    }
+
+    #[test]
+    fn everyone_without_patterns() {
+        assert_eq!(
+            matching(&filter(&[], &[], &[], false)),
+            ["Alice Smith", "Bob", "Carol"]
+        );
+    }
+
+    #[test]
+    fn literal_patterns_ignore_case() {
+        assert_eq!(
+            matching(&filter(&["ALICE"], &[], &[], false)),
+            ["Alice Smith"]
+        );
+        assert_eq!(matching(&filter(&["<bob@"], &[], &[], false)), ["Bob"]);
+        // the dot is literal, not any character
+        assert!(matching(&filter(&["example.co."], &[], &[], false)).is_empty());
+    }
+
+    #[test]
+    fn regex_patterns() {
+        assert_eq!(
+            matching(&filter(&["SMITH|example.org"], &[], &[], true)),
+            ["Alice Smith", "Bob"]
+        );
+        assert_eq!(
+            matching(&filter(&["^(bob|carol) "], &[], &[], true)),
+            ["Bob", "Carol"]
+        );
+        assert_eq!(matching(&filter(&[], &["\\.ORG$"], &[], true)), ["Bob"]);
+        assert!(AuthorFilter::new(&strings(&["("]), &[], &[], true, false).is_err());
+    }
+
+    #[test]
+    fn emails_only_match_the_email() {
+        assert_eq!(
+            matching(&filter(&[], &["example.com"], &[], false)),
+            ["Alice Smith", "Carol"]
+        );
+        assert!(matching(&filter(&[], &["smith"], &[], false)).is_empty());
+    }
+
+    #[test]
+    fn included_patterns_add_up() {
+        assert_eq!(
+            matching(&filter(&["bob"], &["alice@"], &[], false)),
+            ["Alice Smith", "Bob"]
+        );
+    }
+
+    #[test]
+    fn excluded_patterns_win() {
+        assert_eq!(
+            matching(&filter(&[], &[], &["bob"], false)),
+            ["Alice Smith", "Carol"]
+        );
+        assert_eq!(
+            matching(&filter(&["example"], &[], &["CAROL"], false)),
+            ["Alice Smith", "Bob"]
+        );
+        assert!(matching(&filter(&[], &["alice@"], &["smith"], false)).is_empty());
+    }
+
+    #[test]
+    fn me_adds_the_configured_identity() {
+        let test = TestRepo::new("authors-me");
+        let mut config = test.repo.config().unwrap();
+        config.set_str("user.name", "alice smith").unwrap();
+        config.set_str("user.email", "nobody@example.net").unwrap();
+
+        let me = AuthorFilter::new(&[], &[], &[], false, true).unwrap();
+        let me = me.resolve(&test.repo).unwrap();
+        assert_eq!(matching(&me), ["Alice Smith"]);
+
+        let me_and_bob = AuthorFilter::new(&strings(&["bob"]), &[], &[], false, true).unwrap();
+        let me_and_bob = me_and_bob.resolve(&test.repo).unwrap();
+        assert_eq!(matching(&me_and_bob), ["Alice Smith", "Bob"]);
+
+        let not_me = AuthorFilter::new(&[], &[], &strings(&["alice"]), false, true).unwrap();
+        let not_me = not_me.resolve(&test.repo).unwrap();
+        assert!(matching(&not_me).is_empty());
+    }
+
+    #[test]
+    fn repository_mailmap_applies_before_the_shared_one() {
+        let mut test = TestRepo::new("identities");
+        let workdir = test.repo.workdir().unwrap().to_path_buf();
+        std::fs::write(
+            workdir.join(".mailmap"),
+            "Alice Smith <alice@home.example> <alice@example.com>\n",
+        )
+        .unwrap();
+        let commit = test.commit("first", &[]);
+        let commit = test.repo.find_commit(commit).unwrap();
+        let shared = "\
+            Alice Smith <alice@work.example> <alice@home.example>\n\
+            Someone Else <else@example.com> <alice@example.com>\n";
+
+        let identities = Identities::new(&test.repo, None).unwrap();
+        assert_eq!(
+            identities.author(&commit).unwrap().to_string(),
+            "Alice Smith <alice@home.example>"
+        );
+
+        let identities = Identities::new(&test.repo, Some(shared)).unwrap();
+        assert_eq!(
+            identities.author(&commit).unwrap().to_string(),
+            "Alice Smith <alice@work.example>"
+        );
+    }
+}
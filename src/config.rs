@@ -0,0 +1,306 @@
+use crate::projects::ProjectRule;
+use crate::{Error, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Defaults read from TOML files, like
+///
+/// ```toml
+/// format = "daily"
+/// tz = "Europe/Berlin"
+/// author = ["alice@example.com"]
+///
+/// [profiles.work]
+/// repos = ["~/src/api", "~/src/web"]
+/// format = "hours"
+///
+/// [profiles.oss]
+/// repos = ["~/oss/timetable"]
+/// author = ["alice@users.noreply.github.com"]
+///
+/// [[projects]]
+/// name = "acme"
+/// repos = ["~/src/acme-*"]
+///
+/// [[projects]]
+/// name = "globex"
+/// repos = ["~/src/monorepo"]
+/// paths = ["clients/globex/"]
+/// ```
+///
+/// The top-level settings apply to every run, and a profile's settings take
+/// precedence over them when it is selected.
+#[derive(Clone, Debug, Default, Deserialize)]
+pub struct Config {
+    #[serde(flatten)]
+    settings: Settings,
+    #[serde(default)]
+    profiles: BTreeMap<String, Settings>,
+}
+
+/// The settings of a config file or one of its profiles. Unset ones are `None`.
+#[derive(Clone, Debug, Default, Deserialize)]
+#[non_exhaustive]
+pub struct Settings {
+    /// Repositories to list when none are given. Relative paths and paths
+    /// starting with `~/` are resolved when the file is loaded.
+    pub repos: Option<Vec<String>>,
+    pub format: Option<String>,
+    /// Author patterns, as for [QueryBuilder::authors](crate::QueryBuilder::authors)
+    pub author: Option<Vec<String>>,
+    /// A time zone, as understood by [Zone](crate::Zone)
+    pub tz: Option<String>,
+    /// Rules for assigning commits to projects, see [ProjectRule]. `~/` at
+    /// the start of their `repos` globs is resolved when the file is loaded.
+    pub projects: Option<Vec<ProjectRule>>,
+}
+
+impl Config {
+    /// Loads `~/.config/timetable/config.toml`, or the same under
+    /// `$XDG_CONFIG_HOME`, and `.timetable.toml` in the current directory,
+    /// which takes precedence. Missing files are skipped.
+    pub fn load() -> Result<Self> {
+        let config_home = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| home_dir().map(|home| home.join(".config")));
+        let mut paths = Vec::new();
+        paths.extend(config_home.map(|dir| dir.join("timetable").join("config.toml")));
+        paths.push(PathBuf::from(".timetable.toml"));
+        Self::load_files(&paths)
+    }
+
+    /// Loads the files at `paths`, each taking precedence over the ones before
+    /// it. Missing files are skipped.
+    pub fn load_files(paths: &[PathBuf]) -> Result<Self> {
+        let mut config = Config::default();
+        for path in paths {
+            let text = match fs::read_to_string(path) {
+                Ok(text) => text,
+                Err(error) if error.kind() == io::ErrorKind::NotFound => continue,
+                Err(source) => {
+                    return Err(Error::ReadConfig {
+                        path: path.clone(),
+                        source,
+                    })
+                }
+            };
+            let mut file: Config =
+                toml::from_str(&text).map_err(|source| Error::InvalidConfig {
+                    path: path.clone(),
+                    source,
+                })?;
+            let dir = path.parent().unwrap_or(Path::new(""));
+            file.settings.resolve_repos(dir);
+            for settings in file.profiles.values_mut() {
+                settings.resolve_repos(dir);
+            }
+            config.settings = config.settings.or(file.settings);
+            for (name, settings) in file.profiles {
+                let merged = match config.profiles.remove(&name) {
+                    Some(earlier) => earlier.or(settings),
+                    None => settings,
+                };
+                config.profiles.insert(name, merged);
+            }
+        }
+        Ok(config)
+    }
+
+    /// The settings to use with the profile called `profile`, if one is
+    /// selected.
+    pub fn settings(&self, profile: Option<&str>) -> Result<Settings> {
+        let Some(name) = profile else {
+            return Ok(self.settings.clone());
+        };
+        let Some(profile) = self.profiles.get(name) else {
+            return Err(Error::UnknownProfile {
+                name: name.to_string(),
+                known: self.profiles.keys().cloned().collect(),
+            });
+        };
+        Ok(self.settings.clone().or(profile.clone()))
+    }
+}
+
+impl Settings {
+    /// These settings, with the ones set in `over` taking their place.
+    fn or(self, over: Settings) -> Settings {
+        Settings {
+            repos: over.repos.or(self.repos),
+            format: over.format.or(self.format),
+            author: over.author.or(self.author),
+            tz: over.tz.or(self.tz),
+            projects: over.projects.or(self.projects),
+        }
+    }
+
+    fn resolve_repos(&mut self, dir: &Path) {
+        for repo in self.repos.iter_mut().flatten() {
+            let path = match repo.strip_prefix("~/") {
+                Some(rest) => match home_dir() {
+                    Some(home) => home.join(rest),
+                    None => continue,
+                },
+                None => dir.join(&*repo),
+            };
+            *repo = path.display().to_string();
+        }
+        // project globs are matched against repository paths as they are
+        // given, so relative ones stay relative
+        let project_repos = self
+            .projects
+            .iter_mut()
+            .flatten()
+            .flat_map(|rule| rule.repos.iter_mut());
+        for glob in project_repos {
+            if let (Some(rest), Some(home)) = (glob.strip_prefix("~/"), home_dir()) {
+                *glob = home.join(rest).display().to_string();
+            }
+        }
+    }
+}
+
+fn home_dir() -> Option<PathBuf> {
+    env::var_os("HOME")
+        .filter(|home| !home.is_empty())
+        .map(PathBuf::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::TempDir;
+
+    const USER: &str = r#"
+format = "daily"
+tz = "Europe/Berlin"
+author = ["alice@example.com"]
+repos = ["~/src/api", "web"]
+
+[profiles.work]
+format = "hours"
+repos = ["../work/api"]
+
+[profiles.oss]
+author = ["alice@users.noreply.github.com"]
+
+[[projects]]
+name = "acme"
+repos = ["~/src/acme-*", "acme-*"]
+"#;
+
+    const PROJECT: &str = r#"
+format = "csv"
+
+[profiles.work]
+tz = "UTC"
+
+[profiles.client]
+repos = ["client"]
+"#;
+
+    /// Loads `USER` from the temp dir and `PROJECT` from a directory below it.
+    fn load(dir: &TempDir) -> Config {
+        let user = dir.path().join("config.toml");
+        let project = dir.path().join("project").join(".timetable.toml");
+        fs::create_dir_all(project.parent().unwrap()).unwrap();
+        fs::write(&user, USER).unwrap();
+        fs::write(&project, PROJECT).unwrap();
+        let missing = dir.path().join("missing.toml");
+        Config::load_files(&[user, missing, project]).unwrap()
+    }
+
+    fn path(path: PathBuf) -> String {
+        path.display().to_string()
+    }
+
+    #[test]
+    fn later_files_win() {
+        let dir = TempDir::new("config-later");
+        let settings = load(&dir).settings(None).unwrap();
+        assert_eq!(settings.format.as_deref(), Some("csv"));
+        assert_eq!(settings.tz.as_deref(), Some("Europe/Berlin"));
+        assert_eq!(settings.author, Some(vec!["alice@example.com".to_string()]));
+    }
+
+    #[test]
+    fn profiles_win_and_merge() {
+        let dir = TempDir::new("config-profiles");
+        let config = load(&dir);
+
+        let work = config.settings(Some("work")).unwrap();
+        assert_eq!(work.format.as_deref(), Some("hours"));
+        assert_eq!(work.tz.as_deref(), Some("UTC"));
+        assert_eq!(work.author, Some(vec!["alice@example.com".to_string()]));
+
+        let oss = config.settings(Some("oss")).unwrap();
+        assert_eq!(oss.format.as_deref(), Some("csv"));
+        assert_eq!(
+            oss.author,
+            Some(vec!["alice@users.noreply.github.com".to_string()])
+        );
+    }
+
+    #[test]
+    fn resolves_repos() {
+        let dir = TempDir::new("config-repos");
+        let config = load(&dir);
+        let home = match home_dir() {
+            Some(home) => path(home.join("src/api")),
+            None => "~/src/api".to_string(),
+        };
+        assert_eq!(
+            config.settings(None).unwrap().repos,
+            Some(vec![home, path(dir.path().join("web"))])
+        );
+        assert_eq!(
+            config.settings(Some("work")).unwrap().repos,
+            Some(vec![path(dir.path().join("../work/api"))])
+        );
+        assert_eq!(
+            config.settings(Some("client")).unwrap().repos,
+            Some(vec![path(dir.path().join("project/client"))])
+        );
+    }
+
+    #[test]
+    fn resolves_only_home_in_project_globs() {
+        let dir = TempDir::new("config-projects");
+        let projects = load(&dir).settings(None).unwrap().projects.unwrap();
+        let home = match home_dir() {
+            Some(home) => path(home.join("src/acme-*")),
+            None => "~/src/acme-*".to_string(),
+        };
+        assert_eq!(projects[0].repos, [home, "acme-*".to_string()]);
+    }
+
+    #[test]
+    fn unknown_profile() {
+        let dir = TempDir::new("config-unknown");
+        let error = load(&dir).settings(Some("home")).unwrap_err();
+        assert!(matches!(
+            &error,
+            Error::UnknownProfile { name, known } if name == "home" && known == &["client", "oss", "work"]
+        ));
+        assert_eq!(
+            error.to_string(),
+            "unknown profile 'home', expected one of client, oss, work"
+        );
+    }
+
+    #[test]
+    fn invalid_file() {
+        let dir = TempDir::new("config-invalid");
+        let file = dir.path().join("config.toml");
+        fs::write(&file, "format = [").unwrap();
+        assert!(matches!(
+            Config::load_files(std::slice::from_ref(&file)),
+            Err(Error::InvalidConfig { path, .. }) if path == file
+        ));
+    }
+}
@@ -0,0 +1,356 @@
+use crate::zone::Zone;
+use crate::{Error, Result};
+use chrono::{DateTime, Datelike, Days, Months, NaiveDate, NaiveDateTime, NaiveTime, Weekday};
+use std::ops::Range;
+
+const ACCEPTED: &str = "one of
+  2026-10-17T09:00:00+02:00         an RFC 3339 time
+  2026-10-17, 2026-W41, 2026-10     a day, ISO week or month
+  today, yesterday, tomorrow        a day relative to now
+  friday, last friday               the last friday up to or before today
+  today 09:00, 2026-10-17 17:30     a time on a day
+  now, 90 minutes ago, 2 weeks ago  a time relative to now
+  this week, last month             also quarter and year";
+
+/// Resolves a date expression to the span of time it names, as a half-open
+/// range of seconds since the epoch.
+///
+/// A time like `2026-10-17T09:00:00+02:00`, `today 09:00` or `2 weeks ago`
+/// names just that second. A day like `2026-10-17` or `yesterday` names the
+/// whole day, and `2026-W41`, `2026-10`, `last week` or `this quarter` name
+/// the whole week, month or quarter. Days start and end at midnight in `zone`,
+/// and relative expressions count from `now`, in seconds since the epoch.
+pub fn parse_span(s: &str, zone: &Zone, now: i64) -> Result<Range<i64>> {
+    let s = s.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(s) {
+        let time = time.timestamp();
+        return Ok(time..time + 1);
+    }
+    let today = zone.wall_clock(now).date();
+    let words = s
+        .split_whitespace()
+        .map(|word| word.to_lowercase())
+        .collect::<Vec<_>>();
+    let words = words.iter().map(|word| word.as_str()).collect::<Vec<_>>();
+    let span =
+        match words.as_slice() {
+            ["now"] => Some(now..now + 1),
+            [count, unit, "ago"] => count
+                .parse()
+                .ok()
+                .and_then(|count| ago(count, unit, zone, now)),
+            ["this", period] => period_containing(period, today, zone),
+            ["last", period @ ("week" | "month" | "quarter" | "year")] => {
+                let previous = match *period {
+                    "week" => today.checked_sub_days(Days::new(7)),
+                    "month" => today.checked_sub_months(Months::new(1)),
+                    "quarter" => today.checked_sub_months(Months::new(3)),
+                    _ => today.checked_sub_months(Months::new(12)),
+                };
+                previous.and_then(|day| period_containing(period, day, zone))
+            }
+            [day @ .., time] if time.contains(':') => parse_day(day, today)
+                .zip(parse_time(time))
+                .map(|(day, time)| {
+                    let time = zone.timestamp(NaiveDateTime::new(day, time));
+                    time..time + 1
+                }),
+            [word] if word.contains("-w") => parse_week(s, zone).ok(),
+            day => parse_day(day, today)
+                .map(|day| days(zone, day, day + Days::new(1)))
+                .or_else(|| parse_month(s, zone).ok()),
+        };
+    span.ok_or_else(|| invalid("date", s, ACCEPTED))
+}
+
+/// Resolves an ISO week like `2026-W41`, or the week a date like `2026-10-17`
+/// falls into.
+pub fn parse_week(s: &str, zone: &Zone) -> Result<Range<i64>> {
+    let monday = match s.to_uppercase().split_once("-W") {
+        Some((year, week)) => year
+            .parse()
+            .ok()
+            .zip(week.parse().ok())
+            .and_then(|(year, week)| NaiveDate::from_isoywd_opt(year, week, Weekday::Mon)),
+        None => NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .ok()
+            .map(|day| day.week(Weekday::Mon).first_day()),
+    }
+    .ok_or_else(|| invalid("week", s, "YYYY-Www or YYYY-MM-DD"))?;
+    Ok(days(zone, monday, monday + Days::new(7)))
+}
+
+/// Resolves a month like `2026-10`, or the month a date like `2026-10-17`
+/// falls into.
+pub fn parse_month(s: &str, zone: &Zone) -> Result<Range<i64>> {
+    let first = NaiveDate::parse_from_str(&format!("{s}-01"), "%Y-%m-%d")
+        .or_else(|_| NaiveDate::parse_from_str(s, "%Y-%m-%d"))
+        .ok()
+        .and_then(|day| day.with_day(1))
+        .ok_or_else(|| invalid("month", s, "YYYY-MM or YYYY-MM-DD"))?;
+    Ok(days(zone, first, first + Months::new(1)))
+}
+
+/// The time between `since` and the end of `until`, both date expressions as
+/// understood by [parse_span]. A date as `until` includes that whole day.
+pub fn parse_time_range(
+    since: Option<&str>,
+    until: Option<&str>,
+    zone: &Zone,
+    now: i64,
+) -> Result<Range<i64>> {
+    let since = match since {
+        Some(date) => parse_span(date, zone, now)?.start,
+        None => 0,
+    };
+
+    let until = match until {
+        Some(date) => parse_span(date, zone, now)?.end,
+        None => i64::MAX,
+    };
+
+    Ok(since..until)
+}
+
+fn invalid(kind: &'static str, input: &str, expected: &'static str) -> Error {
+    Error::InvalidDate {
+        kind,
+        input: input.to_string(),
+        expected,
+    }
+}
+
+/// A day given as one or two words: a date, `today`, `yesterday`,
+/// `tomorrow`, a weekday or `last` and a weekday.
+fn parse_day(words: &[&str], today: NaiveDate) -> Option<NaiveDate> {
+    match words {
+        ["today"] => Some(today),
+        ["yesterday"] => today.pred_opt(),
+        ["tomorrow"] => today.succ_opt(),
+        ["last", weekday] => last_weekday(weekday, today, false),
+        [word] => NaiveDate::parse_from_str(word, "%Y-%m-%d")
+            .ok()
+            .or_else(|| last_weekday(word, today, true)),
+        _ => None,
+    }
+}
+
+fn parse_time(s: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(s, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(s, "%H:%M:%S"))
+        .ok()
+}
+
+/// The most recent day named `weekday` before `today`, or up to `today` if
+/// `including_today`.
+fn last_weekday(weekday: &str, today: NaiveDate, including_today: bool) -> Option<NaiveDate> {
+    let weekday = weekday.parse::<Weekday>().ok()?;
+    let start = if including_today {
+        today
+    } else {
+        today.pred_opt()?
+    };
+    let back = (7 + start.weekday().num_days_from_monday() - weekday.num_days_from_monday()) % 7;
+    start.checked_sub_days(Days::new(back.into()))
+}
+
+/// The time `count` units before `now`. Days and longer go by the calendar
+/// in `zone`, so `1 day ago` is the same wall clock time yesterday.
+fn ago(count: u32, unit: &str, zone: &Zone, now: i64) -> Option<Range<i64>> {
+    let seconds = match unit.trim_end_matches('s') {
+        "second" => 1,
+        "minute" => 60,
+        "hour" => 3600,
+        unit => {
+            let wall_clock = zone.wall_clock(now);
+            let then = match unit {
+                "day" => wall_clock.checked_sub_days(Days::new(count.into())),
+                "week" => wall_clock.checked_sub_days(Days::new(7 * u64::from(count))),
+                "month" => wall_clock.checked_sub_months(Months::new(count)),
+                "year" => wall_clock.checked_sub_months(Months::new(12 * count)),
+                _ => None,
+            }?;
+            let time = zone.timestamp(then);
+            return Some(time..time + 1);
+        }
+    };
+    let time = now - seconds * i64::from(count);
+    Some(time..time + 1)
+}
+
+/// The week, month, quarter or year that `day` falls into.
+fn period_containing(period: &str, day: NaiveDate, zone: &Zone) -> Option<Range<i64>> {
+    let (first, end) = match period {
+        "week" => {
+            let monday = day.week(Weekday::Mon).first_day();
+            (monday, monday + Days::new(7))
+        }
+        "month" => {
+            let first = day.with_day(1)?;
+            (first, first + Months::new(1))
+        }
+        "quarter" => {
+            let first = NaiveDate::from_ymd_opt(day.year(), day.month0() / 3 * 3 + 1, 1)?;
+            (first, first + Months::new(3))
+        }
+        "year" => {
+            let first = NaiveDate::from_ymd_opt(day.year(), 1, 1)?;
+            (first, first + Months::new(12))
+        }
+        _ => return None,
+    };
+    Some(days(zone, first, end))
+}
+
+/// The time from midnight at the start of `first` to midnight at the start of
+/// `end`.
+fn days(zone: &Zone, first: NaiveDate, end: NaiveDate) -> Range<i64> {
+    let midnight = |day: NaiveDate| zone.timestamp(day.and_hms_opt(0, 0, 0).unwrap());
+    midnight(first)..midnight(end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn berlin() -> Zone {
+        Zone::Named(chrono_tz::Europe::Berlin)
+    }
+
+    fn at(time: &str) -> i64 {
+        DateTime::parse_from_rfc3339(time).unwrap().timestamp()
+    }
+
+    #[test]
+    fn until_date_includes_the_whole_day() {
+        let now = at("2026-10-18T12:00:00Z");
+        assert_eq!(
+            parse_time_range(Some("2026-10-01"), Some("2026-10-17"), &berlin(), now).unwrap(),
+            at("2026-10-01T00:00:00+02:00")..at("2026-10-18T00:00:00+02:00")
+        );
+        assert_eq!(
+            parse_time_range(None, Some("2026-10-17"), &Zone::Utc, now).unwrap(),
+            0..at("2026-10-18T00:00:00Z")
+        );
+        assert_eq!(
+            parse_time_range(None, Some("2026-10-17T09:00:00Z"), &Zone::Utc, now).unwrap(),
+            0..at("2026-10-17T09:00:01Z")
+        );
+    }
+
+    #[test]
+    fn iso_week_runs_monday_to_monday() {
+        let week = at("2026-10-05T00:00:00+02:00")..at("2026-10-12T00:00:00+02:00");
+        assert_eq!(parse_week("2026-W41", &berlin()).unwrap(), week);
+        assert_eq!(parse_week("2026-w41", &berlin()).unwrap(), week);
+        assert_eq!(parse_week("2026-10-11", &berlin()).unwrap(), week);
+        assert_eq!(parse_span("2026-W41", &berlin(), 0).unwrap(), week);
+    }
+
+    #[test]
+    fn days_across_daylight_saving_changes() {
+        let spring = parse_span("2026-03-29", &berlin(), 0).unwrap();
+        assert_eq!(
+            spring,
+            at("2026-03-29T00:00:00+01:00")..at("2026-03-30T00:00:00+02:00")
+        );
+        assert_eq!(spring.end - spring.start, 23 * 60 * 60);
+
+        let autumn = parse_span("2026-10-25", &berlin(), 0).unwrap();
+        assert_eq!(
+            autumn,
+            at("2026-10-25T00:00:00+02:00")..at("2026-10-26T00:00:00+01:00")
+        );
+        assert_eq!(autumn.end - autumn.start, 25 * 60 * 60);
+    }
+
+    #[test]
+    fn relative_days() {
+        // a Monday
+        let now = at("2026-10-19T15:00:00Z");
+        let day = |s| parse_span(s, &Zone::Utc, now).unwrap();
+        let whole_day = |date| {
+            let midnight = at(&format!("{date}T00:00:00Z"));
+            midnight..midnight + 24 * 60 * 60
+        };
+        assert_eq!(day("today"), whole_day("2026-10-19"));
+        assert_eq!(day("yesterday"), whole_day("2026-10-18"));
+        assert_eq!(day("Tomorrow"), whole_day("2026-10-20"));
+        assert_eq!(day("monday"), whole_day("2026-10-19"));
+        assert_eq!(day("last monday"), whole_day("2026-10-12"));
+        assert_eq!(day("last sunday"), whole_day("2026-10-18"));
+        assert_eq!(day("friday"), whole_day("2026-10-16"));
+    }
+
+    #[test]
+    fn relative_times() {
+        let now = at("2026-10-19T15:00:00Z");
+        let time = |s| parse_span(s, &Zone::Utc, now).unwrap().start;
+        assert_eq!(time("now"), now);
+        assert_eq!(time("90 minutes ago"), at("2026-10-19T13:30:00Z"));
+        assert_eq!(time("1 day ago"), at("2026-10-18T15:00:00Z"));
+        assert_eq!(time("2 weeks ago"), at("2026-10-05T15:00:00Z"));
+        assert_eq!(time("1 month ago"), at("2026-09-19T15:00:00Z"));
+    }
+
+    #[test]
+    fn time_on_a_day() {
+        let now = at("2026-10-19T15:00:00Z");
+        assert_eq!(
+            parse_span("today 09:00", &berlin(), now).unwrap(),
+            at("2026-10-19T09:00:00+02:00")..at("2026-10-19T09:00:01+02:00")
+        );
+        assert_eq!(
+            parse_span("2026-12-01 17:30:15", &berlin(), now)
+                .unwrap()
+                .start,
+            at("2026-12-01T17:30:15+01:00")
+        );
+    }
+
+    #[test]
+    fn periods_across_a_year_boundary() {
+        let now = at("2027-01-10T12:00:00Z");
+        let span = |s| parse_span(s, &Zone::Utc, now).unwrap();
+        assert_eq!(
+            span("this quarter"),
+            at("2027-01-01T00:00:00Z")..at("2027-04-01T00:00:00Z")
+        );
+        assert_eq!(
+            span("last quarter"),
+            at("2026-10-01T00:00:00Z")..at("2027-01-01T00:00:00Z")
+        );
+        assert_eq!(
+            span("last month"),
+            at("2026-12-01T00:00:00Z")..at("2027-01-01T00:00:00Z")
+        );
+        assert_eq!(
+            span("last year"),
+            at("2026-01-01T00:00:00Z")..at("2027-01-01T00:00:00Z")
+        );
+        assert_eq!(
+            span("this week"),
+            at("2027-01-04T00:00:00Z")..at("2027-01-11T00:00:00Z")
+        );
+    }
+
+    #[test]
+    fn invalid_date() {
+        let error = parse_span("next tuesday", &Zone::Utc, 0).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            format!("invalid date 'next tuesday', expected {ACCEPTED}")
+        );
+        assert!(error.to_string().starts_with(
+            "invalid date 'next tuesday', expected one of\n  2026-10-17T09:00:00+02:00"
+        ));
+        assert_eq!(
+            parse_week("2026-W54", &Zone::Utc).unwrap_err().to_string(),
+            "invalid week '2026-W54', expected YYYY-Www or YYYY-MM-DD"
+        );
+        assert_eq!(
+            parse_month("2026-13", &Zone::Utc).unwrap_err().to_string(),
+            "invalid month '2026-13', expected YYYY-MM or YYYY-MM-DD"
+        );
+    }
+}
@@ -0,0 +1,125 @@
+use crate::{Error, Result};
+use git2::Repository;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Finds the git repositories at or below `root`.
+///
+/// Both working trees and bare repositories are found, and working trees are
+/// searched for nested repositories as well. Directories named in `skip` are
+/// not entered, and neither is anything more than `max_depth` levels below
+/// `root`. Symlinks are not followed.
+pub fn discover_repositories(
+    root: &Path,
+    skip: &[String],
+    max_depth: usize,
+) -> Result<Vec<PathBuf>> {
+    if !root.is_dir() {
+        return Err(Error::NotADirectory(root.to_path_buf()));
+    }
+    // unreadable directories below the root are skipped
+    let mut repositories = Vec::new();
+    visit(root, skip, max_depth, &mut repositories);
+    Ok(repositories)
+}
+
+fn visit(dir: &Path, skip: &[String], depth: usize, repositories: &mut Vec<PathBuf>) {
+    if dir.join(".git").exists() {
+        repositories.push(dir.to_path_buf());
+    } else if is_bare_repository(dir) {
+        repositories.push(dir.to_path_buf());
+        return;
+    }
+    if depth == 0 {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let mut children = entries
+        .filter_map(|entry| entry.ok())
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .filter(|entry| {
+            let name = entry.file_name();
+            name != ".git" && !skip.iter().any(|skip| name == skip.as_str())
+        })
+        .map(|entry| entry.path())
+        .collect::<Vec<_>>();
+    children.sort();
+    for child in children {
+        visit(&child, skip, depth - 1, repositories);
+    }
+}
+
+fn is_bare_repository(dir: &Path) -> bool {
+    // cheap check first, so we don't ask libgit2 about every directory
+    dir.join("HEAD").is_file()
+        && dir.join("objects").is_dir()
+        && dir.join("refs").is_dir()
+        && Repository::open_bare(dir).is_ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::TempDir;
+
+    #[test]
+    fn finds_working_trees_nested_and_bare_repositories() {
+        let dir = TempDir::new("discover");
+        let root = dir.path();
+        for path in ["app", "app/nested", "app/node_modules/dep", "a/b/c/deep"] {
+            Repository::init(root.join(path)).unwrap();
+        }
+        Repository::init_bare(root.join("mirror.git")).unwrap();
+        fs::create_dir_all(root.join("docs/drafts")).unwrap();
+        let skip = ["node_modules".to_string()];
+
+        assert_eq!(
+            discover_repositories(root, &skip, 3).unwrap(),
+            [
+                root.join("app"),
+                root.join("app/nested"),
+                root.join("mirror.git")
+            ]
+        );
+        assert_eq!(
+            discover_repositories(root, &skip, 4).unwrap(),
+            [
+                root.join("a/b/c/deep"),
+                root.join("app"),
+                root.join("app/nested"),
+                root.join("mirror.git"),
+            ]
+        );
+        assert_eq!(
+            discover_repositories(root, &[], 3).unwrap(),
+            [
+                root.join("app"),
+                root.join("app/nested"),
+                root.join("app/node_modules/dep"),
+                root.join("mirror.git"),
+            ]
+        );
+    }
+
+    #[test]
+    fn root_can_be_a_repository() {
+        let dir = TempDir::new("discover-root");
+        Repository::init(dir.path()).unwrap();
+        assert_eq!(
+            discover_repositories(dir.path(), &[], 0).unwrap(),
+            [dir.path().to_path_buf()]
+        );
+    }
+
+    #[test]
+    fn root_must_be_a_directory() {
+        let dir = TempDir::new("discover-missing");
+        let missing = dir.path().join("missing");
+        assert!(matches!(
+            discover_repositories(&missing, &[], 5),
+            Err(Error::NotADirectory(path)) if path == missing
+        ));
+    }
+}
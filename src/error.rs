@@ -0,0 +1,66 @@
+use std::io;
+use std::path::PathBuf;
+
+/// What can go wrong while building or running a [Query](crate::Query).
+#[derive(Debug, thiserror::Error)]
+#[non_exhaustive]
+pub enum Error {
+    #[error("failed to open repository {path}")]
+    OpenRepository { path: String, source: git2::Error },
+
+    #[error(transparent)]
+    Git(#[from] git2::Error),
+
+    #[error("invalid revision '{spec}'")]
+    InvalidRevision { spec: String, source: git2::Error },
+
+    /// A date, week or month that couldn't be read, with a description of
+    /// what is accepted instead.
+    #[error("invalid {kind} '{input}', expected {expected}")]
+    InvalidDate {
+        kind: &'static str,
+        input: String,
+        expected: &'static str,
+    },
+
+    #[error(transparent)]
+    InvalidRegex(#[from] regex::Error),
+
+    #[error("invalid glob '{glob}'")]
+    InvalidGlob {
+        glob: String,
+        source: glob::PatternError,
+    },
+
+    #[error("invalid template: {0}")]
+    InvalidTemplate(String),
+
+    #[error("unknown format '{name}', expected one of {}", .known.join(", "))]
+    UnknownFormat { name: String, known: Vec<String> },
+
+    #[error("failed to read {}", .path.display())]
+    ReadConfig { path: PathBuf, source: io::Error },
+
+    #[error("invalid config {}", .path.display())]
+    InvalidConfig {
+        path: PathBuf,
+        source: toml::de::Error,
+    },
+
+    #[error("unknown profile '{name}', expected one of {}", .known.join(", "))]
+    UnknownProfile { name: String, known: Vec<String> },
+
+    /// Two query options that can't be used together.
+    #[error("{0} and {1} can't be used together")]
+    Conflict(&'static str, &'static str),
+
+    /// The query includes the configured identity, but a repository has
+    /// neither `user.name` nor `user.email` in its git config.
+    #[error("no user.name or user.email in the git config of {path}")]
+    MissingIdentity { path: String },
+
+    #[error("not a directory: {}", .0.display())]
+    NotADirectory(PathBuf),
+}
+
+pub type Result<T, E = Error> = std::result::Result<T, E>;
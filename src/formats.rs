@@ -0,0 +1,471 @@
+use crate::hours::{GroupBy, Hours};
+use crate::record::CommitRecord;
+use crate::table::{write_csv, write_tsv, Column};
+use crate::template::Template;
+use crate::zone::Zone;
+use crate::{Error, Result};
+use iter_tools::Itertools;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::io::{self, Write};
+
+/// Writes a list of commits in some output format.
+pub trait Formatter {
+    /// Writes `commits`, which are sorted by date.
+    fn write(&self, out: &mut dyn Write, commits: &[CommitRecord]) -> io::Result<()>;
+}
+
+/// Settings that formats take what they need from.
+#[derive(Clone, Debug)]
+#[non_exhaustive]
+pub struct FormatOptions {
+    /// The zone times are shown and days are counted in
+    pub zone: Zone,
+    /// Also show the other of author and committer time when they are more
+    /// than this many seconds apart, in the `flat` and `daily` formats
+    pub show_both_dates: Option<i64>,
+    /// The columns of the `csv` and `tsv` formats, [Column::DEFAULT] if empty
+    pub columns: Vec<Column>,
+    /// Longest pause between two commits of a work session, in seconds, for
+    /// the `hours` format
+    pub max_gap: i64,
+    /// Seconds credited to the first commit of a work session, for the `hours`
+    /// format
+    pub first_commit: i64,
+    /// What the `hours` format totals by, besides the day
+    pub group_by: GroupBy,
+    /// The line written for each commit by the `template` format, see
+    /// [Template]
+    pub template: Option<String>,
+    /// A line written by the `template` format before the first commit and
+    /// whenever it changes from one commit to the next, like `{date:%A %F}`
+    pub header_template: Option<String>,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            zone: Zone::Local,
+            show_both_dates: None,
+            columns: Vec::new(),
+            max_gap: 2 * 60 * 60,
+            first_commit: 2 * 60 * 60,
+            group_by: GroupBy::Repo,
+            template: None,
+            header_template: None,
+        }
+    }
+}
+
+type Factory = Box<dyn Fn(&FormatOptions) -> Result<Box<dyn Formatter>> + Send + Sync>;
+
+/// The output formats by name.
+///
+/// [FormatRegistry::default] has the built-in formats: `flat`, `daily`,
+/// `json`, `ndjson`, `csv`, `tsv`, `hours` and `template`.
+pub struct FormatRegistry {
+    formats: BTreeMap<String, Factory>,
+}
+
+impl FormatRegistry {
+    /// A registry without any formats.
+    pub fn empty() -> Self {
+        Self {
+            formats: BTreeMap::new(),
+        }
+    }
+
+    /// Registers a format under `name`, replacing any format of that name.
+    /// `factory` makes the formatter from the options it is used with, and
+    /// fails if they don't suit the format.
+    pub fn register<F>(&mut self, name: &str, factory: F)
+    where
+        F: Fn(&FormatOptions) -> Result<Box<dyn Formatter>> + Send + Sync + 'static,
+    {
+        self.formats.insert(name.to_string(), Box::new(factory));
+    }
+
+    /// The formatter for the format called `name`.
+    pub fn get(&self, name: &str, options: &FormatOptions) -> Result<Box<dyn Formatter>> {
+        let Some(factory) = self.formats.get(name) else {
+            return Err(Error::UnknownFormat {
+                name: name.to_string(),
+                known: self.names().map(String::from).collect(),
+            });
+        };
+        factory(options)
+    }
+
+    /// The names of all formats, in alphabetical order.
+    pub fn names(&self) -> impl Iterator<Item = &str> {
+        self.formats.keys().map(|name| name.as_str())
+    }
+}
+
+impl Default for FormatRegistry {
+    fn default() -> Self {
+        let mut registry = Self::empty();
+        registry.register("flat", |options| Ok(Box::new(Flat(options.clone()))));
+        registry.register("daily", |options| Ok(Box::new(Daily(options.clone()))));
+        registry.register("json", |options| {
+            Ok(Box::new(Json {
+                zone: options.zone.clone(),
+                lines: false,
+            }))
+        });
+        registry.register("ndjson", |options| {
+            Ok(Box::new(Json {
+                zone: options.zone.clone(),
+                lines: true,
+            }))
+        });
+        registry.register("csv", |options| {
+            Ok(Box::new(Table {
+                options: options.clone(),
+                csv: true,
+            }))
+        });
+        registry.register("tsv", |options| {
+            Ok(Box::new(Table {
+                options: options.clone(),
+                csv: false,
+            }))
+        });
+        registry.register("hours", |options| {
+            Ok(Box::new(HoursFormat(options.clone())))
+        });
+        registry.register("template", |options| {
+            let Some(template) = &options.template else {
+                return Err(Error::InvalidTemplate(
+                    "the template format needs a template".to_string(),
+                ));
+            };
+            Ok(Box::new(TemplateFormat {
+                template: Template::parse(template)?,
+                header: options
+                    .header_template
+                    .as_deref()
+                    .map(Template::parse)
+                    .transpose()?,
+                zone: options.zone.clone(),
+            }))
+        });
+        registry
+    }
+}
+
+/// One line per commit.
+struct Flat(FormatOptions);
+
+impl Formatter for Flat {
+    fn write(&self, out: &mut dyn Write, commits: &[CommitRecord]) -> io::Result<()> {
+        let zone = &self.0.zone;
+        for commit in commits {
+            writeln!(
+                out,
+                "{}\t{}\t{}\t{}\t{}\t{}\t{}{}",
+                commit.date(zone).naive_local(),
+                commit.repo,
+                commit.branch,
+                commit.commit,
+                commit.summary,
+                commit.author,
+                merge_column(commit),
+                other_date_column(commit, self.0.show_both_dates, zone),
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The commits grouped under a line for each day.
+struct Daily(FormatOptions);
+
+impl Formatter for Daily {
+    fn write(&self, out: &mut dyn Write, commits: &[CommitRecord]) -> io::Result<()> {
+        let zone = &self.0.zone;
+        for (date, commits) in &commits.iter().group_by(|x| x.date(zone).date_naive()) {
+            writeln!(out, "{}", date)?;
+            for commit in commits {
+                let time = commit.date(zone).time();
+                writeln!(
+                    out,
+                    "\t\t{}\t{}\t{}\t{}\t{}\t{}{}",
+                    time,
+                    commit.repo,
+                    commit.branch,
+                    commit.summary,
+                    commit.author,
+                    merge_column(commit),
+                    other_date_column(commit, self.0.show_both_dates, zone),
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A JSON array of commits, or one JSON object per line.
+struct Json {
+    zone: Zone,
+    lines: bool,
+}
+
+impl Formatter for Json {
+    fn write(&self, out: &mut dyn Write, commits: &[CommitRecord]) -> io::Result<()> {
+        if self.lines {
+            for commit in commits {
+                serde_json::to_writer(&mut *out, &JsonCommit::new(commit, &self.zone))?;
+                writeln!(out)?;
+            }
+        } else {
+            let commits = commits
+                .iter()
+                .map(|commit| JsonCommit::new(commit, &self.zone))
+                .collect::<Vec<_>>();
+            serde_json::to_writer_pretty(&mut *out, &commits)?;
+            writeln!(out)?;
+        }
+        Ok(())
+    }
+}
+
+/// A commit as written by the `json` and `ndjson` formats, with the date
+/// spelled out in addition to the raw seconds.
+#[derive(Serialize)]
+struct JsonCommit<'a> {
+    timestamp: String,
+    #[serde(flatten)]
+    commit: &'a CommitRecord,
+}
+
+impl<'a> JsonCommit<'a> {
+    fn new(commit: &'a CommitRecord, zone: &Zone) -> Self {
+        Self {
+            timestamp: commit.date(zone).to_rfc3339(),
+            commit,
+        }
+    }
+}
+
+struct Table {
+    options: FormatOptions,
+    csv: bool,
+}
+
+impl Formatter for Table {
+    fn write(&self, mut out: &mut dyn Write, commits: &[CommitRecord]) -> io::Result<()> {
+        let columns = if self.options.columns.is_empty() {
+            Column::DEFAULT
+        } else {
+            &self.options.columns
+        };
+        if self.csv {
+            write_csv(&mut out, commits, columns, &self.options.zone)
+        } else {
+            write_tsv(&mut out, commits, columns, &self.options.zone)
+        }
+    }
+}
+
+struct HoursFormat(FormatOptions);
+
+impl Formatter for HoursFormat {
+    fn write(&self, mut out: &mut dyn Write, commits: &[CommitRecord]) -> io::Result<()> {
+        let options = &self.0;
+        let hours = Hours::estimate(
+            commits,
+            &options.zone,
+            options.max_gap,
+            options.first_commit,
+            options.group_by,
+        );
+        hours.write(&mut out)
+    }
+}
+
+struct TemplateFormat {
+    template: Template,
+    header: Option<Template>,
+    zone: Zone,
+}
+
+impl Formatter for TemplateFormat {
+    fn write(&self, out: &mut dyn Write, commits: &[CommitRecord]) -> io::Result<()> {
+        let mut last_header = None;
+        for commit in commits {
+            if let Some(header) = &self.header {
+                let header = header.render(commit, &self.zone);
+                if last_header.as_ref() != Some(&header) {
+                    writeln!(out, "{header}")?;
+                    last_header = Some(header);
+                }
+            }
+            writeln!(out, "{}", self.template.render(commit, &self.zone))?;
+        }
+        Ok(())
+    }
+}
+
+/// The column marking merge commits in the `flat` and `daily` formats.
+fn merge_column(commit: &CommitRecord) -> &'static str {
+    if commit.merge {
+        "merge"
+    } else {
+        ""
+    }
+}
+
+/// An extra column with the time that isn't `date`, if it differs from `date`
+/// by more than `threshold` seconds, see [FormatOptions::show_both_dates].
+fn other_date_column(commit: &CommitRecord, threshold: Option<i64>, zone: &Zone) -> String {
+    let Some(threshold) = threshold else {
+        return String::new();
+    };
+    if (commit.author_date - commit.committer_date).abs() <= threshold {
+        return "\t".to_string();
+    }
+    if commit.date == commit.author_date && commit.offset == commit.author_offset {
+        let committed = zone.localize(commit.committer_date, commit.committer_offset);
+        format!("\tcommitted {}", committed.naive_local())
+    } else {
+        let authored = zone.localize(commit.author_date, commit.author_offset);
+        format!("\tauthored {}", authored.naive_local())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::Value;
+
+    fn write(format: &str, options: &FormatOptions, commits: &[CommitRecord]) -> String {
+        let formatter = FormatRegistry::default().get(format, options).unwrap();
+        let mut out = Vec::new();
+        formatter.write(&mut out, commits).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    fn commits() -> Vec<CommitRecord> {
+        let mut first = CommitRecord::test("api", "alice", "2026-10-17T09:05:30+02:00");
+        first.summary = "Fix the login".to_string();
+        first.message = "Fix the login\n\nThe session expired too early.\n".to_string();
+        let second = CommitRecord::test("web", "bob", "2026-10-17T10:00:00-04:00");
+        vec![first, second]
+    }
+
+    #[test]
+    fn json_and_ndjson() {
+        let mut options = FormatOptions {
+            zone: Zone::Commit,
+            ..FormatOptions::default()
+        };
+
+        let json = write("json", &options, &commits());
+        let Value::Array(array) = serde_json::from_str(&json).unwrap() else {
+            panic!("not an array: {json}");
+        };
+        let ndjson = write("ndjson", &options, &commits());
+        let lines = ndjson
+            .lines()
+            .map(|line| serde_json::from_str::<Value>(line).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(lines, array);
+
+        assert_eq!(array.len(), 2);
+        assert_eq!(array[0]["timestamp"], "2026-10-17T09:05:30+02:00");
+        assert_eq!(array[1]["timestamp"], "2026-10-17T10:00:00-04:00");
+        assert_eq!(
+            array[0]["message"],
+            "Fix the login\n\nThe session expired too early.\n"
+        );
+        assert_eq!(array[0]["summary"], "Fix the login");
+        assert_eq!(array[0]["repo"], "api");
+        assert_eq!(array[0]["offset"], 120);
+
+        options.zone = Zone::Utc;
+        let ndjson = write("ndjson", &options, &commits());
+        assert!(ndjson.ends_with('\n'));
+        let first = serde_json::from_str::<Value>(ndjson.lines().next().unwrap()).unwrap();
+        assert_eq!(first["timestamp"], "2026-10-17T07:05:30+00:00");
+    }
+
+    /// Writes how many commits there are.
+    struct Count(&'static str);
+
+    impl Formatter for Count {
+        fn write(&self, out: &mut dyn Write, commits: &[CommitRecord]) -> io::Result<()> {
+            writeln!(out, "{} {}", self.0, commits.len())
+        }
+    }
+
+    fn output(registry: &FormatRegistry, format: &str) -> String {
+        let formatter = registry.get(format, &FormatOptions::default()).unwrap();
+        let mut out = Vec::new();
+        formatter.write(&mut out, &commits()).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn register_adds_and_replaces_formats() {
+        let mut registry = FormatRegistry::default();
+        registry.register("count", |_| Ok(Box::new(Count("commits:"))));
+        assert_eq!(output(&registry, "count"), "commits: 2\n");
+        assert!(registry.names().any(|name| name == "count"));
+
+        registry.register("flat", |_| Ok(Box::new(Count("flat:"))));
+        assert_eq!(output(&registry, "flat"), "flat: 2\n");
+        assert_eq!(registry.names().filter(|name| *name == "flat").count(), 1);
+    }
+
+    #[test]
+    fn unknown_format_lists_the_known_ones() {
+        let error = FormatRegistry::default()
+            .get("yaml", &FormatOptions::default())
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "unknown format 'yaml', expected one of csv, daily, flat, hours, json, ndjson, \
+             template, tsv"
+        );
+
+        let mut registry = FormatRegistry::empty();
+        registry.register("count", |_| Ok(Box::new(Count(""))));
+        let error = registry
+            .get("flat", &FormatOptions::default())
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "unknown format 'flat', expected one of count"
+        );
+    }
+
+    #[test]
+    fn template_format_needs_a_template() {
+        let registry = FormatRegistry::default();
+        let error = registry
+            .get("template", &FormatOptions::default())
+            .err()
+            .unwrap();
+        assert_eq!(
+            error.to_string(),
+            "invalid template: the template format needs a template"
+        );
+
+        let options = FormatOptions {
+            template: Some("{repo} {author}".to_string()),
+            header_template: Some("{date:%F}".to_string()),
+            zone: Zone::Utc,
+            ..FormatOptions::default()
+        };
+        let formatter = registry.get("template", &options).unwrap();
+        let mut out = Vec::new();
+        formatter.write(&mut out, &commits()).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "2026-10-17\napi alice\nweb bob\n"
+        );
+    }
+}
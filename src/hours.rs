@@ -0,0 +1,213 @@
+use crate::zone::Zone;
+use crate::CommitRecord;
+use chrono::NaiveDate;
+use clap::ValueEnum;
+use std::collections::{BTreeMap, HashMap};
+use std::io::{self, Write};
+
+/// Estimated working time in seconds, per day and repository or project.
+#[derive(Debug, Default)]
+pub struct Hours {
+    per_day_and_group: BTreeMap<(NaiveDate, String), i64>,
+}
+
+/// What to total the hours by, besides the day.
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum GroupBy {
+    #[default]
+    Repo,
+    /// The project, with commits outside of all projects totalled as
+    /// "No project"
+    Project,
+}
+
+impl Hours {
+    /// Estimates the working time from the commit times, similar to git-hours.
+    ///
+    /// The commits of each author are split into sessions wherever two
+    /// commits are more than `max_gap` seconds apart. Within a session, each
+    /// commit is credited with the time since the previous one. The first
+    /// commit of a session is credited with `first_commit` seconds, for the
+    /// work that went into it before the session's first commit. Expects the
+    /// commits sorted by date, and counts days in `zone`.
+    pub fn estimate(
+        commits: &[CommitRecord],
+        zone: &Zone,
+        max_gap: i64,
+        first_commit: i64,
+        group_by: GroupBy,
+    ) -> Self {
+        let mut hours = Self::default();
+        let mut previous = HashMap::new();
+        for commit in commits {
+            let last = previous.insert(commit.author.as_str(), commit.date);
+            let worked = match last {
+                Some(last) if commit.date - last <= max_gap => commit.date - last,
+                _ => first_commit,
+            };
+            let group = match group_by {
+                GroupBy::Repo => commit.repo.clone(),
+                GroupBy::Project => commit
+                    .project
+                    .as_deref()
+                    .unwrap_or("No project")
+                    .to_string(),
+            };
+            let key = (commit.date(zone).date_naive(), group);
+            *hours.per_day_and_group.entry(key).or_default() += worked;
+        }
+        hours
+    }
+
+    /// Writes the hours per day, broken down by repository or project,
+    /// followed by the hours per repository or project and the total.
+    pub fn write(&self, out: &mut impl Write) -> io::Result<()> {
+        let mut per_group = BTreeMap::<&str, i64>::new();
+        let mut current_day = None;
+        for ((day, group), seconds) in &self.per_day_and_group {
+            if current_day != Some(day) {
+                writeln!(out, "{}\t{}", day, format_hours(self.day_total(day)))?;
+                current_day = Some(day);
+            }
+            writeln!(out, "\t{}\t{}", group, format_hours(*seconds))?;
+            *per_group.entry(group).or_default() += seconds;
+        }
+        writeln!(out)?;
+        for (group, seconds) in per_group {
+            writeln!(out, "{}\t{}", group, format_hours(seconds))?;
+        }
+        writeln!(out)?;
+        writeln!(
+            out,
+            "total\t{}",
+            format_hours(self.per_day_and_group.values().sum())
+        )
+    }
+
+    fn day_total(&self, day: &NaiveDate) -> i64 {
+        self.per_day_and_group
+            .iter()
+            .filter(|((d, _), _)| d == day)
+            .map(|(_, seconds)| seconds)
+            .sum()
+    }
+}
+
+fn format_hours(seconds: i64) -> String {
+    format!("{:.2}", seconds as f64 / 3600.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const MAX_GAP: i64 = 2 * 60 * 60;
+    const FIRST_COMMIT: i64 = 60 * 60;
+
+    fn estimate(commits: &[CommitRecord], zone: &Zone) -> Vec<(String, String, i64)> {
+        Hours::estimate(commits, zone, MAX_GAP, FIRST_COMMIT, GroupBy::Repo)
+            .per_day_and_group
+            .into_iter()
+            .map(|((day, group), seconds)| (day.to_string(), group, seconds / 60))
+            .collect()
+    }
+
+    fn by(author: &str, date: &str) -> CommitRecord {
+        CommitRecord::test("api", author, date)
+    }
+
+    #[test]
+    fn credits_first_commit_of_a_session() {
+        let commits = [by("alice", "2026-10-16T09:00:00Z")];
+        assert_eq!(
+            estimate(&commits, &Zone::Utc),
+            [("2026-10-16".to_string(), "api".to_string(), 60)]
+        );
+    }
+
+    #[test]
+    fn splits_sessions_at_max_gap() {
+        let commits = [
+            by("alice", "2026-10-16T09:00:00Z"),
+            by("alice", "2026-10-16T09:30:00Z"),
+            // exactly max_gap later, still the same session
+            by("alice", "2026-10-16T11:30:00Z"),
+            // more than max_gap later, a new session
+            by("alice", "2026-10-16T13:31:00Z"),
+        ];
+        assert_eq!(
+            estimate(&commits, &Zone::Utc),
+            [(
+                "2026-10-16".to_string(),
+                "api".to_string(),
+                60 + 30 + 120 + 60
+            )]
+        );
+    }
+
+    #[test]
+    fn keeps_sessions_per_author() {
+        let commits = [
+            by("alice", "2026-10-16T09:00:00Z"),
+            by("bob", "2026-10-16T09:10:00Z"),
+            by("alice", "2026-10-16T09:40:00Z"),
+            by("bob", "2026-10-16T10:00:00Z"),
+        ];
+        assert_eq!(
+            estimate(&commits, &Zone::Utc),
+            [(
+                "2026-10-16".to_string(),
+                "api".to_string(),
+                60 + 60 + 40 + 50
+            )]
+        );
+    }
+
+    #[test]
+    fn sessions_crossing_midnight() {
+        let commits = [
+            by("alice", "2026-10-16T23:30:00+02:00"),
+            by("alice", "2026-10-17T00:15:00+02:00"),
+        ];
+        assert_eq!(
+            estimate(&commits, &Zone::Commit),
+            [
+                ("2026-10-16".to_string(), "api".to_string(), 60),
+                ("2026-10-17".to_string(), "api".to_string(), 45),
+            ]
+        );
+        // the same commits are both on the 16th in UTC
+        assert_eq!(
+            estimate(&commits, &Zone::Utc),
+            [("2026-10-16".to_string(), "api".to_string(), 105)]
+        );
+    }
+
+    #[test]
+    fn groups_by_project() {
+        let mut web = by("alice", "2026-10-16T09:30:00Z");
+        web.repo = "web".to_string();
+        web.project = Some("acme".to_string());
+        let commits = [by("alice", "2026-10-16T09:00:00Z"), web];
+        let hours = Hours::estimate(
+            &commits,
+            &Zone::Utc,
+            MAX_GAP,
+            FIRST_COMMIT,
+            GroupBy::Project,
+        );
+        let mut out = Vec::new();
+        hours.write(&mut out).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "2026-10-16\t1.50\n\
+             \tNo project\t1.00\n\
+             \tacme\t0.50\n\
+             \n\
+             No project\t1.00\n\
+             acme\t0.50\n\
+             \n\
+             total\t1.50\n"
+        );
+    }
+}
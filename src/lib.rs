@@ -0,0 +1,51 @@
+//! Lists the commits made across many git repositories, attributed to the
+//! branches they were made on.
+//!
+//! ```no_run
+//! use timetable::{parse_span, Query, Zone};
+//!
+//! let now = chrono::Utc::now().timestamp();
+//! let commits = Query::builder()
+//!     .repositories(["../api", "../web"])
+//!     .time_range(parse_span("last week", &Zone::Local, now)?)
+//!     .authors(["alice@example.com"])
+//!     .no_merges(true)
+//!     .build()?
+//!     .run()?;
+//! for commit in &commits {
+//!     println!("{} {} {}", commit.date(&Zone::Local), commit.repo, commit.summary);
+//! }
+//! # Ok::<_, timetable::Error>(())
+//! ```
+
+mod attribution;
+mod authors;
+mod config;
+mod dates;
+mod discover;
+mod error;
+mod formats;
+mod hours;
+mod paths;
+mod projects;
+mod query;
+mod record;
+mod refs;
+mod table;
+mod template;
+#[cfg(test)]
+mod test_repo;
+mod zone;
+
+pub use config::{Config, Settings};
+pub use dates::{parse_month, parse_span, parse_time_range, parse_week};
+pub use discover::discover_repositories;
+pub use error::{Error, Result};
+pub use formats::{FormatOptions, FormatRegistry, Formatter};
+pub use hours::{GroupBy, Hours};
+pub use projects::ProjectRule;
+pub use query::{Query, QueryBuilder};
+pub use record::{CommitRecord, DateSource};
+pub use table::{write_csv, write_tsv, Column};
+pub use template::Template;
+pub use zone::Zone;
@@ -1,48 +1,14 @@
-use anyhow::{bail, Result};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
 use clap::Parser;
-use git2::{BranchType, Commit, Repository};
-use iter_tools::Itertools;
-use std::fmt::Debug;
-use std::ops::Range;
-
-fn list_commits(repo_path: String, time_range: &Range<i64>, author: &Option<String>) -> Result<Vec<RepoAndCommit>> {
-    let repo = Repository::open(&repo_path)?;
-    let branches = repo.branches(Some(BranchType::Local))?;
-    let mut commits = Vec::new();
-
-    for branch in branches {
-        let (branch, _) = branch?;
-
-        let branch_name = branch.name()?.unwrap_or("No branch").to_string();
-        let branch_oid = branch.get().peel_to_commit()?.id();
-
-        let mut revwalk = repo.revwalk()?;
-        revwalk.push(branch_oid)?;
-
-        for oid in revwalk {
-            let oid = oid?;
-            let commit = repo.find_commit(oid)?;
-            let date = commit.time().seconds();
-
-            if date < time_range.start || date > time_range.end {
-                continue;
-            }
-
-            let repo_and_commit =
-                RepoAndCommit::new(repo_path.clone(), branch_name.clone(), commit);
-
-            if let Some(author) = author {
-                if !repo_and_commit.author.contains(author) {
-                    continue;
-                }
-            }
-
-            commits.push(repo_and_commit);
-        }
-    }
-
-    Ok(commits)
-}
+use std::collections::HashSet;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use timetable::{
+    discover_repositories, parse_month, parse_span, parse_time_range, parse_week, Column, Config,
+    DateSource, FormatOptions, FormatRegistry, GroupBy, Query, Zone,
+};
 
 /// Simple program to greet a person
 #[derive(Parser, Debug)]
@@ -50,122 +16,294 @@ fn list_commits(repo_path: String, time_range: &Range<i64>, author: &Option<Stri
 struct Args {
     repositories: Vec<String>,
 
+    /// Start date or time, like 2026-10-17, yesterday, last monday, 2 weeks ago
+    /// or this month
     #[arg(short, long)]
     since: Option<String>,
 
+    /// End date or time, a day, week or month is included as a whole
     #[arg(short, long)]
     until: Option<String>,
 
+    /// Only this day, week or month, like 2026-10-17, 2026-W41, yesterday or
+    /// last week
+    #[arg(long, group = "period", conflicts_with_all = ["since", "until"])]
+    on: Option<String>,
+
+    /// Only this ISO week, like 2026-W41, or the week of a date
+    #[arg(long, group = "period", conflicts_with_all = ["since", "until"])]
+    week: Option<String>,
+
+    /// Only this month, like 2026-10, or the month of a date
+    #[arg(long, group = "period", conflicts_with_all = ["since", "until"])]
+    month: Option<String>,
+
+    /// Output format: flat, daily, json, ndjson, csv, tsv, hours or template
     #[arg(short, long)]
     format: Option<String>,
 
+    /// Template for each line of the template format, like
+    /// '{date:%H:%M} {repo|basename} {summary|trunc:60}'. Placeholders name
+    /// the fields of the json format, and can be followed by the filters
+    /// date:FORMAT, trunc:N, pad:N, lpad:N and basename
+    #[arg(long, conflicts_with = "template_file")]
+    template: Option<String>,
+
+    /// File to read the template for each line from
+    #[arg(long)]
+    template_file: Option<PathBuf>,
+
+    /// Template for a header line, written before the first commit and
+    /// whenever it changes, like '{date:%A %F}'
+    #[arg(long)]
+    header_template: Option<String>,
+
+    /// Comma separated columns of the csv and tsv formats
+    #[arg(long, value_enum, value_delimiter = ',')]
+    columns: Vec<Column>,
+
+    /// Only commits by an author whose "Name <email>" contains this
     #[arg(short, long)]
-    author: Option<String>,
-}
+    author: Vec<String>,
 
-#[derive(Debug)]
-struct RepoAndCommit {
-    message: String,
-    summary: String,
-    author: String,
-    commit: String,
-    branch: String,
-    repo: String,
-    date: i64,
-}
+    /// Only commits by an author whose email contains this
+    #[arg(long)]
+    author_email: Vec<String>,
 
-impl RepoAndCommit {
-    fn new<'a>(repo: String, branch: String, commit: Commit<'a>) -> Self {
-        Self {
-            summary: commit.summary().unwrap_or("No summary").to_string(),
-            message: commit.message().unwrap_or("No message").to_string(),
-            author: commit.author().to_string(),
-            commit: commit.id().to_string(),
-            date: commit.time().seconds(),
-            repo,
-            branch,
-        }
-    }
+    /// Skip commits by an author whose "Name <email>" contains this
+    #[arg(long)]
+    exclude_author: Vec<String>,
 
-    fn date(&self) -> chrono::NaiveDateTime {
-        chrono::NaiveDateTime::from_timestamp_opt(self.date, 0).unwrap()
-    }
-}
+    /// Read the author patterns as regular expressions
+    #[arg(long)]
+    author_regex: bool,
 
-fn parse_lenient(s: &str) -> Result<i64> {
-    let date = chrono::DateTime::parse_from_rfc3339(s)
-        .map(|dt| dt.timestamp())
-        .or_else(|_| {
-            chrono::NaiveDate::parse_from_str(s, "%Y-%m-%d")
-                .map(|d| d.and_hms_opt(0, 0, 0).unwrap().timestamp())
-        })?;
+    /// Only commits by user.name or user.email from each repository's git
+    /// config, in addition to any --author
+    #[arg(long)]
+    me: bool,
 
-    Ok(date)
-}
+    /// Only commits whose message matches this regular expression, where ^
+    /// and $ match at the start and end of every line
+    #[arg(long, value_name = "REGEX")]
+    grep: Vec<String>,
 
-fn parse_time_range(since: Option<&str>, until: Option<&str>) -> Result<Range<i64>> {
-    let since = match since {
-        Some(date) => parse_lenient(date),
-        None => Ok(0),
-    }?;
+    /// Skip the commits matching --grep instead
+    #[arg(long, requires = "grep")]
+    invert_grep: bool,
 
-    let until = match until {
-        Some(date) => parse_lenient(date),
-        None => Ok(i64::MAX),
-    }?;
+    /// Mailmap file to unify author identities in all repositories, on top of
+    /// each repository's own .mailmap
+    #[arg(long)]
+    mailmap: Option<PathBuf>,
 
-    Ok(since..until)
+    /// Refs to list commits from: local, remote, tags, all for all three, or a
+    /// glob like refs/remotes/origin/*
+    #[arg(long, value_delimiter = ',', default_values_t = ["local".to_string()])]
+    refs: Vec<String>,
+
+    /// Only list refs matching this glob, like feature/*
+    #[arg(long)]
+    branch: Vec<String>,
+
+    /// Skip refs matching this glob, like dependabot/*
+    #[arg(long)]
+    exclude_branch: Vec<String>,
+
+    /// Revision range to list instead of the refs, like v1.4.0..v1.5.0,
+    /// main...feature or "^main feature", quoted so that it's one argument
+    #[arg(long, allow_hyphen_values = true)]
+    rev: Vec<String>,
+
+    /// Directory to search for repositories, in addition to the ones given
+    #[arg(long)]
+    scan: Vec<PathBuf>,
+
+    /// Directory name to skip when searching for repositories
+    #[arg(long, default_values_t = ["node_modules".to_string(), "target".to_string()])]
+    skip_dir: Vec<String>,
+
+    /// How many levels below a --scan directory to search
+    #[arg(long, default_value_t = 5)]
+    max_depth: usize,
+
+    /// Longest pause, in minutes, between two commits of the same work session
+    #[arg(long, default_value_t = 120)]
+    max_gap: i64,
+
+    /// Minutes of work credited to the first commit of a work session
+    #[arg(long, default_value_t = 120)]
+    first_commit: i64,
+
+    /// What the hours format totals by, besides the day: repo, or project as
+    /// configured in the config file
+    #[arg(long, value_enum, default_value_t = GroupBy::Repo)]
+    group_by: GroupBy,
+
+    /// Which time of a commit to go by
+    #[arg(long, value_enum, default_value_t = DateSource::Author)]
+    date_source: DateSource,
+
+    /// Also show the other of author and committer time, when the two are
+    /// more than this many minutes apart
+    #[arg(
+        long,
+        value_name = "MINUTES",
+        num_args = 0..=1,
+        require_equals = true,
+        default_missing_value = "60"
+    )]
+    show_both_dates: Option<i64>,
+
+    /// Time zone for displaying times, grouping by day and reading dates: local,
+    /// commit, utc, or an IANA name like Europe/Berlin [default: local]
+    #[arg(long)]
+    tz: Option<Zone>,
+
+    /// Profile from the config file to take repositories, format, authors and
+    /// time zone from
+    #[arg(short, long)]
+    profile: Option<String>,
+
+    /// Time to resolve relative dates like yesterday against, instead of the
+    /// current time, in RFC 3339 format
+    #[arg(long)]
+    now: Option<String>,
+
+    /// Number of repositories to scan in parallel, defaults to the number of CPUs
+    #[arg(short, long)]
+    jobs: Option<usize>,
+
+    /// Skip merge commits
+    #[arg(long)]
+    no_merges: bool,
+
+    /// Only list merge commits
+    #[arg(long, conflicts_with = "no_merges")]
+    merges_only: bool,
+
+    /// Follow only the first parent of merge commits, leaving out the commits
+    /// merged in from other branches
+    #[arg(long)]
+    first_parent: bool,
+
+    /// Only commits that change files matching these pathspecs, like src/ or
+    /// :!vendor/
+    #[arg(last = true, value_name = "PATHSPEC")]
+    paths: Vec<String>,
 }
 
 fn main() -> Result<()> {
     let opts: Args = Args::parse();
 
-    let since = opts.since.as_deref();
-    let until = opts.until.as_deref();
-    let time_range = parse_time_range(since, until)?;
-    let repositories = opts.repositories;
-    let format = opts.format.map(|x| x).unwrap_or("flat".to_string());
-    let author = opts.author;
-
-    let mut commits = Vec::new();
-    for repo_path in repositories {
-        commits.extend(list_commits(repo_path, &time_range, &author)?);
+    // what is given on the command line takes precedence over the config
+    let settings = Config::load()?.settings(opts.profile.as_deref())?;
+    let zone = match (opts.tz, &settings.tz) {
+        (Some(zone), _) => zone,
+        (None, Some(tz)) => tz.parse().map_err(anyhow::Error::msg)?,
+        (None, None) => Zone::Local,
+    };
+    let now = match &opts.now {
+        Some(now) => DateTime::parse_from_rfc3339(now)?.timestamp(),
+        None => Utc::now().timestamp(),
+    };
+    let time_range = if let Some(day) = &opts.on {
+        parse_span(day, &zone, now)?
+    } else if let Some(week) = &opts.week {
+        parse_week(week, &zone)?
+    } else if let Some(month) = &opts.month {
+        parse_month(month, &zone)?
+    } else {
+        parse_time_range(opts.since.as_deref(), opts.until.as_deref(), &zone, now)?
+    };
+    let mut repositories = opts.repositories;
+    if repositories.is_empty() && opts.scan.is_empty() {
+        repositories = settings.repos.unwrap_or_default();
     }
-    commits.sort_by_key(|c| c.date);
-    match format.as_str() {
-        "flat" => {
-            for commit in commits {
-                println!(
-                    "{}\t{}\t{}\t{}\t{}\t{}",
-                    commit.date(),
-                    commit.repo,
-                    commit.branch,
-                    commit.commit,
-                    commit.summary,
-                    commit.author,
-                );
-            }
-        }
-        "daily" => {
-            commits
-                .into_iter()
-                .group_by(|x| x.date().date())
-                .into_iter()
-                .for_each(|(date, commits)| {
-                    println!("{}", date);
-                    for commit in commits {
-                        let time = commit.date().time();
-                        println!(
-                            "\t\t{}\t{}\t{}\t{}\t{}",
-                            time, commit.repo, commit.branch, commit.summary, commit.author
-                        );
-                    }
-                });
+    for root in &opts.scan {
+        for repo_path in discover_repositories(root, &opts.skip_dir, opts.max_depth)? {
+            repositories.push(repo_path.display().to_string());
         }
-        _ => {
-            bail!("unknown format: {}", format);
+    }
+    // a repository that is both given and found by --scan, or found from two
+    // --scan directories, is listed once, as it was first named
+    let mut seen = HashSet::new();
+    repositories.retain(|path| {
+        let path = Path::new(path);
+        seen.insert(path.canonicalize().unwrap_or_else(|_| path.to_path_buf()))
+    });
+    let template = match &opts.template_file {
+        Some(path) => {
+            let template = fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?;
+            // the line break at the end of the file is not part of the template
+            let template = template.strip_suffix('\n').unwrap_or(&template);
+            Some(template.strip_suffix('\r').unwrap_or(template).to_string())
         }
+        None => opts.template,
+    };
+    // a template on the command line means the template format, whatever the
+    // config says
+    let format = match (opts.format, &template, settings.format) {
+        (Some(format), _, _) => format,
+        (None, Some(_), _) => "template".to_string(),
+        (None, None, Some(format)) => format,
+        (None, None, None) => "flat".to_string(),
+    };
+    let mailmap = match &opts.mailmap {
+        Some(path) => Some(
+            fs::read_to_string(path)
+                .with_context(|| format!("failed to read {}", path.display()))?,
+        ),
+        None => None,
+    };
+    // --author, --author-email and --me all replace the authors of the config
+    let authors = if opts.author.is_empty() && opts.author_email.is_empty() && !opts.me {
+        settings.author.unwrap_or_default()
+    } else {
+        opts.author
+    };
+    let mut query = Query::builder()
+        .repositories(repositories)
+        .time_range(time_range)
+        .authors(authors)
+        .author_emails(opts.author_email)
+        .excluded_authors(opts.exclude_author)
+        .author_regex(opts.author_regex)
+        .me(opts.me)
+        .date_source(opts.date_source)
+        .grep(opts.grep)
+        .invert_grep(opts.invert_grep)
+        .refs(opts.refs)
+        .branches(opts.branch)
+        .excluded_branches(opts.exclude_branch)
+        .revs(opts.rev)
+        .paths(opts.paths)
+        .no_merges(opts.no_merges)
+        .merges_only(opts.merges_only)
+        .first_parent(opts.first_parent)
+        .projects(settings.projects.unwrap_or_default());
+    if let Some(mailmap) = mailmap {
+        query = query.mailmap(mailmap);
     }
+    if let Some(jobs) = opts.jobs {
+        query = query.jobs(jobs);
+    }
+
+    let registry = FormatRegistry::default();
+    let mut options = FormatOptions::default();
+    options.zone = zone;
+    options.show_both_dates = opts.show_both_dates.map(|minutes| minutes * 60);
+    options.columns = opts.columns;
+    options.max_gap = opts.max_gap * 60;
+    options.first_commit = opts.first_commit * 60;
+    options.group_by = opts.group_by;
+    options.template = template;
+    options.header_template = opts.header_template;
+    let formatter = registry.get(&format, &options)?;
+
+    let commits = query.build()?.run()?;
+    formatter.write(&mut io::stdout().lock(), &commits)?;
 
     Ok(())
 }
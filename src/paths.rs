@@ -0,0 +1,130 @@
+use crate::Result;
+use git2::{Commit, DiffOptions, Pathspec, PathspecFlags, Repository};
+
+/// Decides whether a commit touches the paths of interest.
+///
+/// Pathspecs are git's, except that the only magic understood is exclusion,
+/// written `:!path`, `:^path` or `:(exclude)path`. A commit touches the paths
+/// if its diff against its first parent, or against the empty tree for a root
+/// commit, changes a file that matches one of the included pathspecs, or any
+/// file if there are none, and doesn't match an excluded one.
+pub struct PathFilter {
+    include: Vec<String>,
+    exclude: Option<Pathspec>,
+}
+
+impl PathFilter {
+    pub fn new(specs: &[String]) -> Result<Self> {
+        let mut include = Vec::new();
+        let mut exclude = Vec::new();
+        for spec in specs {
+            match [":!", ":^", ":(exclude)"]
+                .iter()
+                .find_map(|magic| spec.strip_prefix(magic))
+            {
+                Some(excluded) => exclude.push(excluded),
+                None => include.push(spec.clone()),
+            }
+        }
+        let exclude = if exclude.is_empty() {
+            None
+        } else {
+            Some(Pathspec::new(exclude)?)
+        };
+        Ok(Self { include, exclude })
+    }
+
+    pub fn touches(&self, repo: &Repository, commit: &Commit) -> Result<bool> {
+        let mut options = DiffOptions::new();
+        for spec in &self.include {
+            options.pathspec(spec);
+        }
+        let parent = match commit.parent(0) {
+            Ok(parent) => Some(parent.tree()?),
+            Err(_) => None,
+        };
+        let diff =
+            repo.diff_tree_to_tree(parent.as_ref(), Some(&commit.tree()?), Some(&mut options))?;
+        let Some(exclude) = &self.exclude else {
+            return Ok(diff.deltas().len() > 0);
+        };
+        Ok(diff.deltas().any(|delta| {
+            [delta.old_file().path(), delta.new_file().path()]
+                .into_iter()
+                .flatten()
+                .any(|path| !exclude.matches_path(path, PathspecFlags::DEFAULT))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::TestRepo;
+
+    /// Which commits of a small history touch `specs`.
+    fn touching(specs: &[&str]) -> Vec<&'static str> {
+        let mut test = TestRepo::new("paths");
+        let root = test.commit_files("root", &[], &[("README", "hi"), ("src/main.rs", "")]);
+        let src = test.commit_files("src", &[root], &[("src/lib.rs", "")]);
+        let vendor = test.commit_files("vendor", &[src], &[("vendor/dep.rs", "")]);
+        let both = test.commit_files(
+            "both",
+            &[vendor],
+            &[("src/lib.rs", "1"), ("vendor/dep.rs", "1")],
+        );
+        let docs = test.commit_files("docs", &[both], &[("docs/guide.md", "")]);
+        let empty = test.commit("empty", &[docs]);
+
+        let specs = specs.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let filter = PathFilter::new(&specs).unwrap();
+        [
+            ("root", root),
+            ("src", src),
+            ("vendor", vendor),
+            ("both", both),
+            ("docs", docs),
+            ("empty", empty),
+        ]
+        .into_iter()
+        .filter(|(_, oid)| {
+            let commit = test.repo.find_commit(*oid).unwrap();
+            filter.touches(&test.repo, &commit).unwrap()
+        })
+        .map(|(name, _)| name)
+        .collect()
+    }
+
+    #[test]
+    fn no_specs() {
+        assert_eq!(touching(&[]), ["root", "src", "vendor", "both", "docs"]);
+    }
+
+    #[test]
+    fn include() {
+        assert_eq!(touching(&["src/"]), ["root", "src", "both"]);
+        assert_eq!(
+            touching(&["vendor", "docs/*.md"]),
+            ["vendor", "both", "docs"]
+        );
+        assert_eq!(touching(&["README"]), ["root"]);
+    }
+
+    #[test]
+    fn exclude() {
+        assert_eq!(touching(&[":!vendor/"]), ["root", "src", "both", "docs"]);
+        assert_eq!(
+            touching(&[":^src/", ":(exclude)docs/"]),
+            ["root", "vendor", "both"]
+        );
+    }
+
+    #[test]
+    fn include_and_exclude() {
+        assert_eq!(
+            touching(&["src/", "vendor/", ":!vendor/"]),
+            ["root", "src", "both"]
+        );
+        assert_eq!(touching(&["src/", ":!src/lib.rs"]), ["root"]);
+    }
+}
@@ -0,0 +1,271 @@
+use crate::{Error, Result};
+use git2::{Commit, Pathspec, PathspecFlags, Repository};
+use glob::{MatchOptions, Pattern};
+use serde::Deserialize;
+use std::path::Path;
+
+/// Assigns commits to a project, like the client they are billed to.
+///
+/// A commit belongs to a project if its repository's path matches one of
+/// `repos`, or there are none, and it changes a file matching one of the
+/// pathspecs in `paths`, or there are none. Of several rules that fit, the
+/// first one wins.
+#[derive(Clone, Debug, Deserialize)]
+#[non_exhaustive]
+pub struct ProjectRule {
+    pub name: String,
+    /// Globs on repository paths, where `*` doesn't match `/`. They are
+    /// matched against both the path as given and the absolute path, so a
+    /// relative glob like `acme-*` only matches repositories given relative to
+    /// the current directory.
+    #[serde(default)]
+    pub repos: Vec<String>,
+    /// Pathspecs within the repository, like `clients/acme/`
+    #[serde(default)]
+    pub paths: Vec<String>,
+}
+
+impl ProjectRule {
+    pub fn new(name: &str, repos: &[String], paths: &[String]) -> Self {
+        Self {
+            name: name.to_string(),
+            repos: repos.to_vec(),
+            paths: paths.to_vec(),
+        }
+    }
+}
+
+/// The project rules, with their repository globs checked.
+#[derive(Clone, Debug, Default)]
+pub(crate) struct Projects {
+    rules: Vec<(ProjectRule, Vec<Pattern>)>,
+}
+
+impl Projects {
+    pub fn new(rules: Vec<ProjectRule>) -> Result<Self> {
+        let rules = rules
+            .into_iter()
+            .map(|rule| {
+                let globs = rule
+                    .repos
+                    .iter()
+                    .map(|glob| {
+                        Pattern::new(glob.trim_end_matches('/')).map_err(|source| {
+                            Error::InvalidGlob {
+                                glob: glob.clone(),
+                                source,
+                            }
+                        })
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                Ok((rule, globs))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { rules })
+    }
+
+    /// The rules that apply to the repository at `repo_path`, matched both as
+    /// given and as an absolute path.
+    pub fn for_repository(&self, repo_path: &str) -> Result<RepositoryProjects> {
+        let given = repo_path.trim_end_matches('/');
+        let absolute = Path::new(repo_path)
+            .canonicalize()
+            .map(|path| path.display().to_string())
+            .unwrap_or_else(|_| given.to_string());
+        let options = MatchOptions {
+            require_literal_separator: true,
+            ..MatchOptions::new()
+        };
+        let mut rules = Vec::new();
+        for (rule, globs) in &self.rules {
+            let matches = globs.is_empty()
+                || globs.iter().any(|glob| {
+                    glob.matches_with(given, options) || glob.matches_with(&absolute, options)
+                });
+            if !matches {
+                continue;
+            }
+            let paths = if rule.paths.is_empty() {
+                None
+            } else {
+                Some(Pathspec::new(&rule.paths)?)
+            };
+            rules.push((rule.name.clone(), paths));
+        }
+        Ok(RepositoryProjects { rules })
+    }
+}
+
+/// The project rules that apply to one repository.
+pub(crate) struct RepositoryProjects {
+    rules: Vec<(String, Option<Pathspec>)>,
+}
+
+impl RepositoryProjects {
+    /// The project `commit` belongs to, if any.
+    pub fn project(&self, repo: &Repository, commit: &Commit) -> Result<Option<String>> {
+        // the files changed are only needed if a rule looks at them
+        let mut changed = None;
+        for (name, paths) in &self.rules {
+            let Some(paths) = paths else {
+                return Ok(Some(name.clone()));
+            };
+            if changed.is_none() {
+                changed = Some(changed_files(repo, commit)?);
+            }
+            let files = changed.as_deref().unwrap_or_default();
+            if files
+                .iter()
+                .any(|file| paths.matches_path(Path::new(file), PathspecFlags::DEFAULT))
+            {
+                return Ok(Some(name.clone()));
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The files `commit` changes compared to its first parent, or that it adds
+/// if it is a root commit.
+fn changed_files(repo: &Repository, commit: &Commit) -> Result<Vec<String>> {
+    let parent = match commit.parent(0) {
+        Ok(parent) => Some(parent.tree()?),
+        Err(_) => None,
+    };
+    let diff = repo.diff_tree_to_tree(parent.as_ref(), Some(&commit.tree()?), None)?;
+    Ok(diff
+        .deltas()
+        .flat_map(|delta| [delta.old_file().path(), delta.new_file().path()])
+        .flatten()
+        .map(|path| path.display().to_string())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::TestRepo;
+    use git2::Oid;
+
+    fn rule(name: &str, repos: &[&str], paths: &[&str]) -> ProjectRule {
+        let strings = |items: &[&str]| items.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        ProjectRule::new(name, &strings(repos), &strings(paths))
+    }
+
+    /// The project of each commit, for the repository at `repo_path`.
+    fn projects(
+        rules: Vec<ProjectRule>,
+        repo_path: &str,
+        test: &TestRepo,
+        commits: &[Oid],
+    ) -> Vec<Option<String>> {
+        let projects = Projects::new(rules).unwrap();
+        let projects = projects.for_repository(repo_path).unwrap();
+        commits
+            .iter()
+            .map(|oid| {
+                let commit = test.repo.find_commit(*oid).unwrap();
+                projects.project(&test.repo, &commit).unwrap()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn repo_globs() {
+        let mut test = TestRepo::new("projects-repos");
+        let commit = [test.commit("first", &[])];
+        let rules = || {
+            vec![
+                rule("acme", &["/src/acme-*"], &[]),
+                rule("local", &["acme-*"], &[]),
+            ]
+        };
+        let project = |path| projects(rules(), path, &test, &commit).remove(0);
+
+        assert_eq!(project("/src/acme-web").as_deref(), Some("acme"));
+        assert_eq!(project("/src/acme-web/").as_deref(), Some("acme"));
+        assert_eq!(project("acme-web").as_deref(), Some("local"));
+        // `*` doesn't match `/`
+        assert_eq!(project("/src/acme-web/api"), None);
+        assert_eq!(project("/src/globex"), None);
+    }
+
+    #[test]
+    fn repo_globs_match_the_absolute_path() {
+        let mut test = TestRepo::new("projects-absolute");
+        let commit = [test.commit("first", &[])];
+        let workdir = test.repo.workdir().unwrap().canonicalize().unwrap();
+        let parent = workdir.parent().unwrap().display().to_string();
+        let name = workdir.file_name().unwrap().to_str().unwrap();
+        let rules = vec![rule("mine", &[&format!("{parent}/{name}")], &[])];
+        let relative = format!("{}/../{name}", workdir.display());
+        assert_eq!(
+            projects(rules, &relative, &test, &commit),
+            [Some("mine".to_string())]
+        );
+    }
+
+    #[test]
+    fn path_rules_pick_the_client() {
+        let mut test = TestRepo::new("projects-paths");
+        let root = test.commit_files("root", &[], &[("README", "")]);
+        let acme = test.commit_files("acme", &[root], &[("clients/acme/a.rs", "")]);
+        let globex = test.commit_files("globex", &[acme], &[("clients/globex/g.rs", "")]);
+        let both = test.commit_files(
+            "both",
+            &[globex],
+            &[("clients/acme/a.rs", "1"), ("clients/globex/g.rs", "1")],
+        );
+        let shared = test.commit_files("shared", &[both], &[("lib/shared.rs", "")]);
+        let rules = vec![
+            rule("globex", &[], &["clients/globex/"]),
+            rule("acme", &["/elsewhere"], &["clients/acme/"]),
+            rule("acme", &[], &["clients/acme/"]),
+        ];
+
+        assert_eq!(
+            projects(
+                rules,
+                "monorepo",
+                &test,
+                &[root, acme, globex, both, shared]
+            ),
+            [
+                None,
+                Some("acme".to_string()),
+                Some("globex".to_string()),
+                Some("globex".to_string()),
+                None,
+            ]
+        );
+    }
+
+    #[test]
+    fn first_rule_wins() {
+        let mut test = TestRepo::new("projects-first");
+        let commit = [test.commit_files("first", &[], &[("clients/acme/a.rs", "")])];
+        let rules = vec![
+            rule("acme", &[], &["clients/acme/"]),
+            rule("catch-all", &[], &[]),
+        ];
+        assert_eq!(
+            projects(rules, "repo", &test, &commit),
+            [Some("acme".to_string())]
+        );
+
+        let rules = vec![
+            rule("catch-all", &[], &[]),
+            rule("acme", &[], &["clients/acme/"]),
+        ];
+        assert_eq!(
+            projects(rules, "repo", &test, &commit),
+            [Some("catch-all".to_string())]
+        );
+    }
+
+    #[test]
+    fn invalid_glob() {
+        let error = Projects::new(vec![rule("acme", &["[acme"], &[])]).unwrap_err();
+        assert!(matches!(error, Error::InvalidGlob { glob, .. } if glob == "[acme"));
+    }
+}
@@ -0,0 +1,412 @@
+use crate::attribution::attribute_branches;
+use crate::authors::{AuthorFilter, Identities};
+use crate::paths::PathFilter;
+use crate::projects::{ProjectRule, Projects};
+use crate::record::{CommitRecord, DateSource};
+use crate::refs::{resolve_revs, select_tips};
+use crate::{Error, Result};
+use git2::Repository;
+use glob::Pattern;
+use regex::{Regex, RegexBuilder};
+use std::cmp::Reverse;
+use std::ops::Range;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+
+/// What to list, and from which repositories. Built with [Query::builder].
+#[derive(Debug)]
+pub struct Query {
+    repositories: Vec<String>,
+    time_range: Range<i64>,
+    authors: AuthorFilter,
+    /// Extra mailmap applied to all repositories, in mailmap format
+    mailmap: Option<String>,
+    date_source: DateSource,
+    /// Only commits whose message matches one of these, if any are given
+    grep: Vec<Regex>,
+    /// Skip the commits matching `grep` instead
+    invert_grep: bool,
+    /// Ref selectors, as understood by [select_tips]
+    refs: Vec<String>,
+    /// Only refs matching one of these, if any are given
+    branches: Vec<Pattern>,
+    exclude_branches: Vec<Pattern>,
+    /// Revision ranges to list instead of the refs, as understood by
+    /// [resolve_revs]
+    revs: Vec<String>,
+    /// Only commits touching these paths, as understood by [PathFilter]
+    paths: Vec<String>,
+    no_merges: bool,
+    merges_only: bool,
+    /// Follow only the first parent of merge commits, leaving out the commits
+    /// they brought in
+    first_parent: bool,
+    projects: Projects,
+    jobs: usize,
+}
+
+impl Query {
+    pub fn builder() -> QueryBuilder {
+        QueryBuilder::default()
+    }
+
+    /// Lists the matching commits of all repositories, oldest first.
+    ///
+    /// Repositories are walked in parallel, each worker opening and walking one
+    /// repository at a time. Commits with the same date are listed in the order
+    /// of the repositories.
+    pub fn run(&self) -> Result<Vec<CommitRecord>> {
+        let repositories = &self.repositories;
+        let next = AtomicUsize::new(0);
+        let (tx, rx) = mpsc::channel();
+        thread::scope(|scope| {
+            for _ in 0..self.jobs.clamp(1, repositories.len().max(1)) {
+                let tx = tx.clone();
+                let next = &next;
+                scope.spawn(move || loop {
+                    let i = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(repo_path) = repositories.get(i) else {
+                        break;
+                    };
+                    let result = self.list(repo_path);
+                    if tx.send((i, result)).is_err() {
+                        break;
+                    }
+                });
+            }
+        });
+        drop(tx);
+
+        let mut results = rx.into_iter().collect::<Vec<_>>();
+        results.sort_by_key(|(i, _)| *i);
+        let mut commits = Vec::new();
+        for (_, result) in results {
+            commits.extend(result?);
+        }
+        commits.sort_by_key(|commit| commit.date);
+        Ok(commits)
+    }
+
+    /// Lists the matching commits of the repository at `repo_path`, newest
+    /// first by committer time.
+    pub fn list(&self, repo_path: &str) -> Result<Vec<CommitRecord>> {
+        let time_range = &self.time_range;
+        let repo = Repository::open(repo_path).map_err(|source| Error::OpenRepository {
+            path: repo_path.to_string(),
+            source,
+        })?;
+        let authors = self.authors.resolve(&repo)?;
+        let identities = Identities::new(&repo, self.mailmap.as_deref())?;
+        let paths = PathFilter::new(&self.paths)?;
+        let projects = self.projects.for_repository(repo_path)?;
+        let (mut tips, hidden) = if self.revs.is_empty() {
+            let tips = select_tips(&repo, &self.refs, &self.branches, &self.exclude_branches)?;
+            (tips, Vec::new())
+        } else {
+            resolve_revs(&repo, &self.revs)?
+        };
+        let names = tips
+            .iter()
+            .map(|tip| (tip.name.clone(), tip.oid))
+            .collect::<Vec<_>>();
+        let attribution = attribute_branches(&repo, &names, time_range.start)?;
+
+        // A single walk over all refs visits every commit once. Without explicit
+        // sorting, libgit2 walks lazily and queues parents by committer date, reading
+        // the commit-graph file when there is one. Setting `Sort::TIME` would make it
+        // load the whole history up front instead. The tips start the queue in the
+        // order they were pushed, so push the newest first.
+        tips.sort_by_key(|tip| Reverse(tip.time));
+        let mut revwalk = repo.revwalk()?;
+        if self.first_parent {
+            revwalk.simplify_first_parent()?;
+        }
+        for tip in &tips {
+            revwalk.push(tip.oid)?;
+        }
+        for oid in hidden {
+            revwalk.hide(oid)?;
+        }
+
+        let mut commits = Vec::new();
+        for oid in revwalk {
+            let oid = oid?;
+            let commit = repo.find_commit(oid)?;
+
+            // everything still queued was committed before this, so we are done. The
+            // author time, if that is what we go by, is normally earlier still.
+            if commit.time().seconds() < time_range.start {
+                break;
+            }
+            let merge = commit.parent_count() > 1;
+            if (self.no_merges && merge) || (self.merges_only && !merge) {
+                continue;
+            }
+            let author = identities.author(&commit)?;
+            let date = match self.date_source {
+                DateSource::Author => author.when().seconds(),
+                DateSource::Committer => commit.time().seconds(),
+            };
+            if date < time_range.start || date >= time_range.end {
+                continue;
+            }
+            if !authors.matches(&author) {
+                continue;
+            }
+            if !self.grep.is_empty() {
+                let message = String::from_utf8_lossy(commit.message_bytes());
+                let matches = self.grep.iter().any(|grep| grep.is_match(&message));
+                if matches == self.invert_grep {
+                    continue;
+                }
+            }
+            if !self.paths.is_empty() && !paths.touches(&repo, &commit)? {
+                continue;
+            }
+
+            let branch_name = attribution
+                .get(&oid)
+                .map_or("No branch", |name| name.as_str())
+                .to_string();
+            let project = projects.project(&repo, &commit)?;
+            commits.push(CommitRecord::new(
+                repo_path.to_string(),
+                branch_name,
+                project,
+                commit,
+                &author,
+                self.date_source,
+            ));
+        }
+
+        Ok(commits)
+    }
+}
+
+/// Collects the options of a [Query]. Patterns are only checked by
+/// [QueryBuilder::build], so the setters can be chained freely.
+#[derive(Clone, Debug, Default)]
+pub struct QueryBuilder {
+    repositories: Vec<String>,
+    time_range: Option<Range<i64>>,
+    authors: Vec<String>,
+    author_emails: Vec<String>,
+    excluded_authors: Vec<String>,
+    author_regex: bool,
+    me: bool,
+    mailmap: Option<String>,
+    date_source: DateSource,
+    grep: Vec<String>,
+    invert_grep: bool,
+    refs: Vec<String>,
+    branches: Vec<String>,
+    excluded_branches: Vec<String>,
+    revs: Vec<String>,
+    paths: Vec<String>,
+    no_merges: bool,
+    merges_only: bool,
+    first_parent: bool,
+    projects: Vec<ProjectRule>,
+    jobs: Option<usize>,
+}
+
+impl QueryBuilder {
+    /// Adds paths of repositories to list commits from.
+    pub fn repositories(mut self, paths: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.repositories.extend(paths.into_iter().map(Into::into));
+        self
+    }
+
+    /// Only commits dated within `range`, in seconds since the epoch. The
+    /// default is all of history.
+    pub fn time_range(mut self, range: Range<i64>) -> Self {
+        self.time_range = Some(range);
+        self
+    }
+
+    /// Only commits by an author whose `Name <email>` matches one of these.
+    pub fn authors(mut self, patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.authors.extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Only commits by an author whose email matches one of these.
+    pub fn author_emails(mut self, patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.author_emails
+            .extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Skips commits by an author whose `Name <email>` matches one of these.
+    pub fn excluded_authors(
+        mut self,
+        patterns: impl IntoIterator<Item = impl Into<String>>,
+    ) -> Self {
+        self.excluded_authors
+            .extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Reads the author patterns as regular expressions instead of text to
+    /// look for. Either way, they match case-insensitively.
+    pub fn author_regex(mut self, regex: bool) -> Self {
+        self.author_regex = regex;
+        self
+    }
+
+    /// Also includes commits by the `user.name` or `user.email` configured in
+    /// each repository.
+    pub fn me(mut self, me: bool) -> Self {
+        self.me = me;
+        self
+    }
+
+    /// A mailmap, in the format of `.mailmap`, applied to all repositories on
+    /// top of their own.
+    pub fn mailmap(mut self, mailmap: impl Into<String>) -> Self {
+        self.mailmap = Some(mailmap.into());
+        self
+    }
+
+    pub fn date_source(mut self, date_source: DateSource) -> Self {
+        self.date_source = date_source;
+        self
+    }
+
+    /// Only commits whose message matches one of these regular expressions,
+    /// where `^` and `$` match at the start and end of every line.
+    pub fn grep(mut self, patterns: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.grep.extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Skips the commits matching [QueryBuilder::grep] instead.
+    pub fn invert_grep(mut self, invert: bool) -> Self {
+        self.invert_grep = invert;
+        self
+    }
+
+    /// Selects the refs to list commits from: `local`, `remote`, `tags`, `all`
+    /// for all three, or globs on full ref names like `refs/remotes/origin/*`.
+    /// The default is `local`.
+    pub fn refs(mut self, selectors: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.refs.extend(selectors.into_iter().map(Into::into));
+        self
+    }
+
+    /// Only refs matching one of these globs. Globs starting with `refs/`
+    /// match the full ref name, all others the short name.
+    pub fn branches(mut self, globs: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.branches.extend(globs.into_iter().map(Into::into));
+        self
+    }
+
+    /// Skips refs matching one of these globs.
+    pub fn excluded_branches(mut self, globs: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.excluded_branches
+            .extend(globs.into_iter().map(Into::into));
+        self
+    }
+
+    /// Lists these revision ranges instead of the refs, like `v1.4.0..v1.5.0`,
+    /// `main...feature` or `^main feature`, where revisions separated by
+    /// whitespace are taken as separate ones.
+    pub fn revs(mut self, specs: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.revs.extend(specs.into_iter().map(Into::into));
+        self
+    }
+
+    /// Only commits changing files that match these pathspecs, which may be
+    /// excluding ones like `:!vendor/`.
+    pub fn paths(mut self, specs: impl IntoIterator<Item = impl Into<String>>) -> Self {
+        self.paths.extend(specs.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn no_merges(mut self, no_merges: bool) -> Self {
+        self.no_merges = no_merges;
+        self
+    }
+
+    pub fn merges_only(mut self, merges_only: bool) -> Self {
+        self.merges_only = merges_only;
+        self
+    }
+
+    /// Follows only the first parent of merge commits.
+    pub fn first_parent(mut self, first_parent: bool) -> Self {
+        self.first_parent = first_parent;
+        self
+    }
+
+    /// Adds rules for assigning commits to projects, which apply in order.
+    pub fn projects(mut self, rules: impl IntoIterator<Item = ProjectRule>) -> Self {
+        self.projects.extend(rules);
+        self
+    }
+
+    /// How many repositories to walk in parallel. The default is the number
+    /// of CPUs.
+    pub fn jobs(mut self, jobs: usize) -> Self {
+        self.jobs = Some(jobs);
+        self
+    }
+
+    /// Checks the patterns and builds the query.
+    pub fn build(self) -> Result<Query> {
+        if self.no_merges && self.merges_only {
+            return Err(Error::Conflict("no_merges", "merges_only"));
+        }
+        let globs = |globs: Vec<String>| {
+            globs
+                .iter()
+                .map(|glob| {
+                    Pattern::new(glob).map_err(|source| Error::InvalidGlob {
+                        glob: glob.clone(),
+                        source,
+                    })
+                })
+                .collect::<Result<Vec<_>>>()
+        };
+        let grep = self
+            .grep
+            .iter()
+            .map(|pattern| RegexBuilder::new(pattern).multi_line(true).build())
+            .collect::<Result<Vec<_>, _>>()?;
+        let refs = if self.refs.is_empty() {
+            vec!["local".to_string()]
+        } else {
+            self.refs
+        };
+        let jobs = match self.jobs {
+            Some(jobs) => jobs,
+            None => thread::available_parallelism().map_or(1, |n| n.get()),
+        };
+        Ok(Query {
+            repositories: self.repositories,
+            time_range: self.time_range.unwrap_or(0..i64::MAX),
+            authors: AuthorFilter::new(
+                &self.authors,
+                &self.author_emails,
+                &self.excluded_authors,
+                self.author_regex,
+                self.me,
+            )?,
+            mailmap: self.mailmap,
+            date_source: self.date_source,
+            grep,
+            invert_grep: self.invert_grep,
+            refs,
+            branches: globs(self.branches)?,
+            exclude_branches: globs(self.excluded_branches)?,
+            revs: self.revs,
+            paths: self.paths,
+            no_merges: self.no_merges,
+            merges_only: self.merges_only,
+            first_parent: self.first_parent,
+            projects: Projects::new(self.projects)?,
+            jobs,
+        })
+    }
+}
@@ -0,0 +1,111 @@
+use crate::zone::Zone;
+use chrono::{DateTime, FixedOffset};
+use clap::ValueEnum;
+use git2::{Commit, Signature};
+use serde::Serialize;
+
+/// A commit found by a [Query](crate::Query), with everything about it that
+/// gets reported.
+#[derive(Clone, Debug, Serialize)]
+#[non_exhaustive]
+pub struct CommitRecord {
+    pub message: String,
+    pub summary: String,
+    /// The author as `Name <email>`, after applying the mailmaps
+    pub author: String,
+    /// The full commit id
+    pub commit: String,
+    /// The branch the commit was made on, as worked out by following
+    /// first-parent chains and merge messages
+    pub branch: String,
+    /// The repository path, as given to the query
+    pub repo: String,
+    /// The project the commit belongs to, see [ProjectRule](crate::ProjectRule)
+    pub project: Option<String>,
+    /// Whether the commit has more than one parent
+    pub merge: bool,
+    /// The author or committer time, whichever the query asked for, in
+    /// seconds since the epoch
+    pub date: i64,
+    /// UTC offset of the time zone `date` was recorded in, in minutes
+    pub offset: i32,
+    pub author_date: i64,
+    pub author_offset: i32,
+    pub committer_date: i64,
+    pub committer_offset: i32,
+}
+
+impl CommitRecord {
+    pub(crate) fn new(
+        repo: String,
+        branch: String,
+        project: Option<String>,
+        commit: Commit,
+        author: &Signature,
+        date_source: DateSource,
+    ) -> Self {
+        let authored = author.when();
+        let committed = commit.time();
+        let date = match date_source {
+            DateSource::Author => authored,
+            DateSource::Committer => committed,
+        };
+        Self {
+            summary: commit.summary().unwrap_or("No summary").to_string(),
+            message: commit.message().unwrap_or("No message").to_string(),
+            author: author.to_string(),
+            commit: commit.id().to_string(),
+            merge: commit.parent_count() > 1,
+            date: date.seconds(),
+            offset: date.offset_minutes(),
+            author_date: authored.seconds(),
+            author_offset: authored.offset_minutes(),
+            committer_date: committed.seconds(),
+            committer_offset: committed.offset_minutes(),
+            repo,
+            branch,
+            project,
+        }
+    }
+
+    /// `date` in `zone`.
+    pub fn date(&self, zone: &Zone) -> DateTime<FixedOffset> {
+        zone.localize(self.date, self.offset)
+    }
+}
+
+#[cfg(test)]
+impl CommitRecord {
+    /// A commit by `author` in `repo` at `date`, an RFC 3339 time, with the
+    /// other fields empty.
+    pub(crate) fn test(repo: &str, author: &str, date: &str) -> Self {
+        let date = DateTime::parse_from_rfc3339(date).unwrap();
+        let offset = date.offset().local_minus_utc() / 60;
+        Self {
+            message: String::new(),
+            summary: String::new(),
+            author: author.to_string(),
+            commit: String::new(),
+            branch: "main".to_string(),
+            repo: repo.to_string(),
+            project: None,
+            merge: false,
+            date: date.timestamp(),
+            offset,
+            author_date: date.timestamp(),
+            author_offset: offset,
+            committer_date: date.timestamp(),
+            committer_offset: offset,
+        }
+    }
+}
+
+/// Which of a commit's two times to go by.
+#[derive(Clone, Copy, Debug, Default, ValueEnum)]
+pub enum DateSource {
+    /// When the change was originally made
+    #[default]
+    Author,
+    /// When the commit was last rewritten, by a rebase, amend or cherry-pick
+    Committer,
+}
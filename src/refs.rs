@@ -0,0 +1,212 @@
+use crate::{Error, Result};
+use git2::{Object, Oid, ReferenceType, Repository, RevparseMode};
+use glob::Pattern;
+use std::collections::BTreeMap;
+
+/// A ref whose history gets listed.
+#[derive(Debug)]
+pub struct Tip {
+    /// Short name of the ref, like `main`, `origin/main` or `v1.0`
+    pub name: String,
+    pub oid: Oid,
+    /// Committer time of the commit the ref points to
+    pub time: i64,
+}
+
+/// Resolves ref selectors to the commits they point to.
+///
+/// A selector is `local`, `remote`, `tags` or `all` for all three, or a glob
+/// on full ref names like `refs/remotes/origin/*`. Other refs, like
+/// `refs/stash` or `refs/notes/*`, are only selected by a glob. Symbolic refs
+/// like `origin/HEAD` are skipped, as are refs that don't point to a commit. A
+/// ref matched by more than one selector is returned once.
+///
+/// If there are `include` patterns, only refs matching one of them are
+/// returned, and refs matching an `exclude` pattern never are. Patterns starting
+/// with `refs/` match the full ref name, all others the short name.
+pub fn select_tips(
+    repo: &Repository,
+    selectors: &[String],
+    include: &[Pattern],
+    exclude: &[Pattern],
+) -> Result<Vec<Tip>> {
+    let mut tips = BTreeMap::new();
+    for selector in selectors {
+        let globs = match selector.as_str() {
+            "local" => vec!["refs/heads/*"],
+            "remote" => vec!["refs/remotes/*"],
+            "tags" => vec!["refs/tags/*"],
+            "all" => vec!["refs/heads/*", "refs/remotes/*", "refs/tags/*"],
+            glob => vec![glob],
+        };
+        let references = globs
+            .into_iter()
+            .map(|glob| repo.references_glob(glob))
+            .collect::<Result<Vec<_>, _>>()?;
+        for reference in references.into_iter().flatten() {
+            let reference = reference?;
+            if reference.kind() == Some(ReferenceType::Symbolic) {
+                continue;
+            }
+            let Some(full_name) = reference.name() else {
+                continue;
+            };
+            if tips.contains_key(full_name) {
+                continue;
+            }
+            let name = reference.shorthand().unwrap_or(full_name);
+            let matches = |pattern: &Pattern| {
+                if pattern.as_str().starts_with("refs/") {
+                    pattern.matches(full_name)
+                } else {
+                    pattern.matches(name)
+                }
+            };
+            let included = include.is_empty() || include.iter().any(matches);
+            if !included || exclude.iter().any(matches) {
+                continue;
+            }
+            let Ok(commit) = reference.peel_to_commit() else {
+                continue;
+            };
+            let tip = Tip {
+                name: name.to_string(),
+                oid: commit.id(),
+                time: commit.time().seconds(),
+            };
+            tips.insert(full_name.to_string(), tip);
+        }
+    }
+    Ok(tips.into_values().collect())
+}
+
+/// Resolves revision ranges in git's syntax to the commits to walk from and
+/// the commits whose history to leave out.
+///
+/// `A..B` walks from `B` and leaves out `A`, `A...B` walks from both and
+/// leaves out their merge base. `^X` leaves out `X`, anything else is walked
+/// from. Each spec may hold several revisions separated by whitespace, like
+/// `^main feature`. The commits walked from are named after the revision that
+/// was given.
+pub fn resolve_revs(repo: &Repository, specs: &[String]) -> Result<(Vec<Tip>, Vec<Oid>)> {
+    let mut tips = Vec::new();
+    let mut hidden = Vec::new();
+    for spec in specs.iter().flat_map(|spec| spec.split_whitespace()) {
+        let invalid = |source| Error::InvalidRevision {
+            spec: spec.to_string(),
+            source,
+        };
+        if let Some(excluded) = spec.strip_prefix('^') {
+            let commit = repo.revparse_single(excluded).map_err(invalid)?;
+            hidden.push(commit.peel_to_commit().map_err(invalid)?.id());
+            continue;
+        }
+        let revspec = repo.revparse(spec).map_err(invalid)?;
+        let mode = revspec.mode();
+        if mode.contains(RevparseMode::SINGLE) {
+            if let Some(from) = revspec.from() {
+                tips.push(tip(from, spec)?);
+            }
+            continue;
+        }
+        // an empty side of a range means HEAD
+        let (left, right) = spec
+            .split_once("...")
+            .or_else(|| spec.split_once(".."))
+            .map(|(left, right)| (name_or_head(left), name_or_head(right)))
+            .unwrap_or_default();
+        let (Some(from), Some(to)) = (revspec.from(), revspec.to()) else {
+            continue;
+        };
+        if mode.contains(RevparseMode::MERGE_BASE) {
+            let (from, to) = (tip(from, left)?, tip(to, right)?);
+            hidden.push(repo.merge_base(from.oid, to.oid).map_err(invalid)?);
+            tips.extend([from, to]);
+        } else {
+            hidden.push(from.peel_to_commit().map_err(invalid)?.id());
+            tips.push(tip(to, right)?);
+        }
+    }
+    Ok((tips, hidden))
+}
+
+fn tip(object: &Object, name: &str) -> Result<Tip> {
+    let commit = object.peel_to_commit()?;
+    Ok(Tip {
+        name: name.to_string(),
+        oid: commit.id(),
+        time: commit.time().seconds(),
+    })
+}
+
+fn name_or_head(name: &str) -> &str {
+    if name.is_empty() {
+        "HEAD"
+    } else {
+        name
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_repo::TestRepo;
+
+    fn names(test: &TestRepo, selectors: &[&str]) -> Vec<String> {
+        let selectors = selectors.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        select_tips(&test.repo, &selectors, &[], &[])
+            .unwrap()
+            .into_iter()
+            .map(|tip| tip.name)
+            .collect()
+    }
+
+    #[test]
+    fn all_selects_branches_remotes_and_tags() {
+        let mut test = TestRepo::new("select-tips");
+        let commit = test.commit("first", &[]);
+        for name in [
+            "refs/heads/main",
+            "refs/remotes/origin/main",
+            "refs/tags/v1.0",
+            "refs/stash",
+            "refs/notes/commits",
+        ] {
+            test.repo.reference(name, commit, true, "test").unwrap();
+        }
+
+        assert_eq!(names(&test, &["all"]), ["main", "origin/main", "v1.0"]);
+        assert_eq!(names(&test, &["local", "tags"]), ["main", "v1.0"]);
+        assert_eq!(names(&test, &["refs/notes/*"]), ["notes/commits"]);
+        assert_eq!(names(&test, &["all", "refs/stash"]).len(), 4);
+    }
+
+    #[test]
+    fn revs_split_on_whitespace() {
+        let mut test = TestRepo::new("resolve-revs");
+        let first = test.commit("first", &[]);
+        let second = test.commit("second", &[first]);
+        let feature = test.commit("feature", &[second]);
+        test.repo
+            .reference("refs/heads/main", second, true, "test")
+            .unwrap();
+        test.repo
+            .reference("refs/heads/feature", feature, true, "test")
+            .unwrap();
+
+        let (tips, hidden) = resolve_revs(&test.repo, &["^main feature".to_string()]).unwrap();
+        let tips = tips
+            .iter()
+            .map(|tip| (tip.name.as_str(), tip.oid))
+            .collect::<Vec<_>>();
+        assert_eq!(tips, [("feature", feature)]);
+        assert_eq!(hidden, [second]);
+
+        let (tips, hidden) = resolve_revs(&test.repo, &["main..feature".to_string()]).unwrap();
+        assert_eq!(tips.len(), 1);
+        assert_eq!(hidden, [second]);
+
+        let error = resolve_revs(&test.repo, &["^main nope".to_string()]).unwrap_err();
+        assert!(error.to_string().contains("'nope'"), "{error}");
+    }
+}
@@ -0,0 +1,181 @@
+use crate::zone::Zone;
+use crate::CommitRecord;
+use clap::ValueEnum;
+use std::io::{self, Write};
+
+/// A column of the `csv` and `tsv` formats.
+#[derive(Clone, Copy, Debug, ValueEnum)]
+pub enum Column {
+    Date,
+    Repo,
+    Project,
+    Branch,
+    Commit,
+    Summary,
+    Author,
+    Message,
+    Merge,
+    AuthorDate,
+    CommitterDate,
+}
+
+impl Column {
+    /// The columns of the `flat` format.
+    pub const DEFAULT: &'static [Column] = &[
+        Column::Date,
+        Column::Repo,
+        Column::Branch,
+        Column::Commit,
+        Column::Summary,
+        Column::Author,
+        Column::Merge,
+    ];
+
+    fn name(self) -> &'static str {
+        match self {
+            Column::Date => "date",
+            Column::Repo => "repo",
+            Column::Project => "project",
+            Column::Branch => "branch",
+            Column::Commit => "commit",
+            Column::Summary => "summary",
+            Column::Author => "author",
+            Column::Message => "message",
+            Column::Merge => "merge",
+            Column::AuthorDate => "author-date",
+            Column::CommitterDate => "committer-date",
+        }
+    }
+
+    fn value(self, commit: &CommitRecord, zone: &Zone) -> String {
+        match self {
+            Column::Date => commit.date(zone).naive_local().to_string(),
+            Column::Repo => commit.repo.clone(),
+            Column::Project => commit.project.clone().unwrap_or_default(),
+            Column::Branch => commit.branch.clone(),
+            Column::Commit => commit.commit.clone(),
+            Column::Summary => commit.summary.clone(),
+            Column::Author => commit.author.clone(),
+            Column::Message => commit.message.clone(),
+            Column::Merge => commit.merge.to_string(),
+            Column::AuthorDate => zone
+                .localize(commit.author_date, commit.author_offset)
+                .naive_local()
+                .to_string(),
+            Column::CommitterDate => zone
+                .localize(commit.committer_date, commit.committer_offset)
+                .naive_local()
+                .to_string(),
+        }
+    }
+}
+
+/// Writes the commits as RFC 4180 CSV, with a header row.
+pub fn write_csv(
+    out: &mut impl Write,
+    commits: &[CommitRecord],
+    columns: &[Column],
+    zone: &Zone,
+) -> io::Result<()> {
+    write_table(out, commits, columns, zone, ",", "\r\n", csv_field)
+}
+
+/// Writes the commits as tab separated values, with a header row. Tabs, line
+/// breaks and backslashes within a field are escaped as `\t`, `\n`, `\r` and `\\`.
+pub fn write_tsv(
+    out: &mut impl Write,
+    commits: &[CommitRecord],
+    columns: &[Column],
+    zone: &Zone,
+) -> io::Result<()> {
+    write_table(out, commits, columns, zone, "\t", "\n", tsv_field)
+}
+
+fn write_table(
+    out: &mut impl Write,
+    commits: &[CommitRecord],
+    columns: &[Column],
+    zone: &Zone,
+    separator: &str,
+    terminator: &str,
+    field: fn(&str) -> String,
+) -> io::Result<()> {
+    let header = columns
+        .iter()
+        .map(|column| field(column.name()))
+        .collect::<Vec<_>>();
+    write!(out, "{}{}", header.join(separator), terminator)?;
+    for commit in commits {
+        let row = columns
+            .iter()
+            .map(|column| field(&column.value(commit, zone)))
+            .collect::<Vec<_>>();
+        write!(out, "{}{}", row.join(separator), terminator)?;
+    }
+    Ok(())
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn tsv_field(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\t' => escaped.push_str("\\t"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn csv_quotes_only_when_needed() {
+        assert_eq!(csv_field("plain text"), "plain text");
+        assert_eq!(csv_field("a,b"), "\"a,b\"");
+        assert_eq!(csv_field("say \"hi\""), "\"say \"\"hi\"\"\"");
+        assert_eq!(csv_field("one\r\ntwo"), "\"one\r\ntwo\"");
+        assert_eq!(csv_field("one\ntwo"), "\"one\ntwo\"");
+        assert_eq!(csv_field("tab\there"), "tab\there");
+        assert_eq!(csv_field("back\\slash"), "back\\slash");
+    }
+
+    #[test]
+    fn tsv_escapes_separators() {
+        assert_eq!(tsv_field("plain, \"text\""), "plain, \"text\"");
+        assert_eq!(tsv_field("a\tb"), "a\\tb");
+        assert_eq!(tsv_field("one\r\ntwo"), "one\\r\\ntwo");
+        assert_eq!(tsv_field("C:\\tmp"), "C:\\\\tmp");
+        assert_eq!(tsv_field("\\t"), "\\\\t");
+    }
+
+    #[test]
+    fn header_row() {
+        let mut out = Vec::new();
+        write_csv(&mut out, &[], Column::DEFAULT, &Zone::Utc).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "date,repo,branch,commit,summary,author,merge\r\n"
+        );
+
+        let mut out = Vec::new();
+        let columns = [Column::AuthorDate, Column::Project, Column::CommitterDate];
+        write_tsv(&mut out, &[], &columns, &Zone::Utc).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "author-date\tproject\tcommitter-date\n"
+        );
+    }
+}
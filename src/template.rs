@@ -0,0 +1,361 @@
+use crate::record::CommitRecord;
+use crate::zone::Zone;
+use crate::{Error, Result};
+use chrono::format::{Item, StrftimeItems};
+use chrono::{DateTime, FixedOffset};
+
+const DEFAULT_DATE_FORMAT: &str = "%Y-%m-%d %H:%M:%S";
+
+/// A line of output made from the fields of a commit, like
+/// `{date:%H:%M} {repo|basename} {summary|trunc:60}`.
+///
+/// A placeholder names a [CommitRecord] field, in the spelling of the `json`
+/// format, followed by filters separated by `|`. The filters are
+///
+/// - `date:FORMAT` formats a time in strftime syntax, which can also be
+///   written as `{date:FORMAT}`,
+/// - `trunc:N` shortens to `N` characters, ending in `…` when something was cut,
+/// - `pad:N` and `lpad:N` pad with spaces to `N` characters, on the right or left,
+/// - `basename` keeps what follows the last `/`.
+///
+/// `{{` and `}}` stand for literal braces, and `\n`, `\t` and `\\` for a line
+/// break, a tab and a backslash.
+#[derive(Clone, Debug)]
+pub struct Template {
+    parts: Vec<Part>,
+}
+
+#[derive(Clone, Debug)]
+enum Part {
+    Text(String),
+    Field(Field, Vec<Filter>),
+}
+
+#[derive(Clone, Copy, Debug)]
+enum Field {
+    Message,
+    Summary,
+    Author,
+    Commit,
+    Branch,
+    Repo,
+    Project,
+    Merge,
+    Date,
+    Offset,
+    AuthorDate,
+    AuthorOffset,
+    CommitterDate,
+    CommitterOffset,
+}
+
+#[derive(Clone, Debug)]
+enum Filter {
+    Date(String),
+    Trunc(usize),
+    Pad(usize),
+    LPad(usize),
+    Basename,
+}
+
+enum Value {
+    Text(String),
+    Time(DateTime<FixedOffset>),
+}
+
+impl Template {
+    pub fn parse(template: &str) -> Result<Self> {
+        let mut parts = Vec::new();
+        let mut text = String::new();
+        let mut chars = template.chars();
+        while let Some(c) = chars.next() {
+            match c {
+                '{' if chars.as_str().starts_with('{') => {
+                    chars.next();
+                    text.push('{');
+                }
+                '}' if chars.as_str().starts_with('}') => {
+                    chars.next();
+                    text.push('}');
+                }
+                '{' => {
+                    let rest = chars.as_str();
+                    let Some(end) = rest.find('}') else {
+                        return Err(invalid(format!("unclosed '{{' in '{template}'")));
+                    };
+                    if !text.is_empty() {
+                        parts.push(Part::Text(std::mem::take(&mut text)));
+                    }
+                    parts.push(parse_placeholder(&rest[..end])?);
+                    chars = rest[end + 1..].chars();
+                }
+                '}' => return Err(invalid(format!("unmatched '}}' in '{template}'"))),
+                '\\' => match chars.next() {
+                    Some('n') => text.push('\n'),
+                    Some('t') => text.push('\t'),
+                    Some('\\') => text.push('\\'),
+                    Some(c) => text.extend(['\\', c]),
+                    None => text.push('\\'),
+                },
+                c => text.push(c),
+            }
+        }
+        if !text.is_empty() {
+            parts.push(Part::Text(text));
+        }
+        Ok(Self { parts })
+    }
+
+    /// Fills in the fields of `commit`, with times in `zone`.
+    pub fn render(&self, commit: &CommitRecord, zone: &Zone) -> String {
+        let mut out = String::new();
+        for part in &self.parts {
+            match part {
+                Part::Text(text) => out.push_str(text),
+                Part::Field(field, filters) => {
+                    let value = filters
+                        .iter()
+                        .fold(field.value(commit, zone), |value, filter| {
+                            filter.apply(value)
+                        });
+                    match value {
+                        Value::Text(text) => out.push_str(&text),
+                        Value::Time(time) => {
+                            out.push_str(&time.format(DEFAULT_DATE_FORMAT).to_string())
+                        }
+                    }
+                }
+            }
+        }
+        out
+    }
+}
+
+fn parse_placeholder(placeholder: &str) -> Result<Part> {
+    let mut filters = placeholder.split('|');
+    let field = filters.next().unwrap_or_default().trim();
+    let (name, arg) = match field.split_once(':') {
+        Some((name, arg)) => (name, Some(arg)),
+        None => (field, None),
+    };
+    let field = Field::parse(name)?;
+    let mut is_time = field.is_time();
+    let mut parsed = Vec::new();
+    if let Some(format) = arg {
+        if !is_time {
+            return Err(invalid(format!(
+                "'{name}' is not a time, so it can't have a format"
+            )));
+        }
+        parsed.push(date_filter(format)?);
+        is_time = false;
+    }
+    for filter in filters {
+        let filter = filter.trim();
+        let (name, arg) = match filter.split_once(':') {
+            Some((name, arg)) => (name, Some(arg)),
+            None => (filter, None),
+        };
+        let count = || {
+            arg.and_then(|arg| arg.parse::<usize>().ok())
+                .ok_or_else(|| invalid(format!("'{name}' needs a number, like {name}:10")))
+        };
+        let filter = match name {
+            "date" if !is_time => {
+                return Err(invalid("'date' only applies to times".to_string()));
+            }
+            "date" => date_filter(arg.unwrap_or(DEFAULT_DATE_FORMAT))?,
+            "trunc" => Filter::Trunc(count()?),
+            "pad" => Filter::Pad(count()?),
+            "lpad" => Filter::LPad(count()?),
+            "basename" => Filter::Basename,
+            name => {
+                return Err(invalid(format!(
+                    "unknown filter '{name}', expected date, trunc, pad, lpad or basename"
+                )))
+            }
+        };
+        parsed.push(filter);
+        is_time = false;
+    }
+    Ok(Part::Field(field, parsed))
+}
+
+fn date_filter(format: &str) -> Result<Filter> {
+    if StrftimeItems::new(format).any(|item| item == Item::Error) {
+        return Err(invalid(format!("invalid date format '{format}'")));
+    }
+    Ok(Filter::Date(format.to_string()))
+}
+
+fn invalid(message: String) -> Error {
+    Error::InvalidTemplate(message)
+}
+
+impl Field {
+    fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "message" => Field::Message,
+            "summary" => Field::Summary,
+            "author" => Field::Author,
+            "commit" => Field::Commit,
+            "branch" => Field::Branch,
+            "repo" => Field::Repo,
+            "project" => Field::Project,
+            "merge" => Field::Merge,
+            "date" => Field::Date,
+            "offset" => Field::Offset,
+            "author_date" => Field::AuthorDate,
+            "author_offset" => Field::AuthorOffset,
+            "committer_date" => Field::CommitterDate,
+            "committer_offset" => Field::CommitterOffset,
+            name => {
+                return Err(invalid(format!(
+                    "unknown field '{name}', expected message, summary, author, commit, \
+                     branch, repo, project, merge, date, offset, author_date, author_offset, \
+                     committer_date or committer_offset"
+                )))
+            }
+        })
+    }
+
+    fn is_time(self) -> bool {
+        matches!(self, Field::Date | Field::AuthorDate | Field::CommitterDate)
+    }
+
+    fn value(self, commit: &CommitRecord, zone: &Zone) -> Value {
+        let text = |text: &str| Value::Text(text.to_string());
+        match self {
+            Field::Message => text(commit.message.trim_end()),
+            Field::Summary => text(&commit.summary),
+            Field::Author => text(&commit.author),
+            Field::Commit => text(&commit.commit),
+            Field::Branch => text(&commit.branch),
+            Field::Repo => text(&commit.repo),
+            Field::Project => text(commit.project.as_deref().unwrap_or_default()),
+            Field::Merge => Value::Text(commit.merge.to_string()),
+            Field::Date => Value::Time(commit.date(zone)),
+            Field::Offset => Value::Text(commit.offset.to_string()),
+            Field::AuthorDate => {
+                Value::Time(zone.localize(commit.author_date, commit.author_offset))
+            }
+            Field::AuthorOffset => Value::Text(commit.author_offset.to_string()),
+            Field::CommitterDate => {
+                Value::Time(zone.localize(commit.committer_date, commit.committer_offset))
+            }
+            Field::CommitterOffset => Value::Text(commit.committer_offset.to_string()),
+        }
+    }
+}
+
+impl Filter {
+    fn apply(&self, value: Value) -> Value {
+        let text = match value {
+            Value::Time(time) => match self {
+                Filter::Date(format) => return Value::Text(time.format(format).to_string()),
+                _ => time.format(DEFAULT_DATE_FORMAT).to_string(),
+            },
+            Value::Text(text) => text,
+        };
+        Value::Text(match self {
+            Filter::Date(_) => text,
+            Filter::Trunc(width) => {
+                if text.chars().count() <= *width {
+                    text
+                } else if *width == 0 {
+                    String::new()
+                } else {
+                    let mut text = text.chars().take(width - 1).collect::<String>();
+                    text.push('…');
+                    text
+                }
+            }
+            Filter::Pad(width) => format!("{text:<width$}"),
+            Filter::LPad(width) => format!("{text:>width$}"),
+            Filter::Basename => text
+                .trim_end_matches('/')
+                .rsplit('/')
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render(template: &str) -> String {
+        let mut commit = CommitRecord::test("/src/api", "alice", "2026-10-17T09:05:30+02:00");
+        commit.summary = "Grüße aus Köln".to_string();
+        Template::parse(template)
+            .unwrap()
+            .render(&commit, &Zone::Commit)
+    }
+
+    fn error(template: &str) -> String {
+        Template::parse(template).unwrap_err().to_string()
+    }
+
+    #[test]
+    fn escapes() {
+        assert_eq!(render("{{summary}} }}"), "{summary} }");
+        assert_eq!(render("{{{author}}}"), "{alice}");
+        assert_eq!(render(r"a\tb\nc\\d\e"), "a\tb\nc\\d\\e");
+    }
+
+    #[test]
+    fn dates() {
+        assert_eq!(render("{date:%H:%M}"), "09:05");
+        assert_eq!(render("{date}"), "2026-10-17 09:05:30");
+        assert_eq!(render("{author_date|date:%F}"), "2026-10-17");
+        assert_eq!(render("{offset}"), "120");
+    }
+
+    #[test]
+    fn chained_filters() {
+        assert_eq!(render("[{repo|basename|pad:5}]"), "[api  ]");
+        assert_eq!(render("[{repo | basename | lpad:5}]"), "[  api]");
+        assert_eq!(render("{date:%A|trunc:3}"), "Sa…");
+        assert_eq!(render("[{date:%H|lpad:4}]"), "[  09]");
+    }
+
+    #[test]
+    fn trunc_counts_characters() {
+        assert_eq!(render("{summary|trunc:5}"), "Grüß…");
+        assert_eq!(render("{summary|trunc:14}"), "Grüße aus Köln");
+        assert_eq!(render("{summary|trunc:1}"), "…");
+        assert_eq!(render("[{summary|trunc:0}]"), "[]");
+    }
+
+    #[test]
+    fn errors() {
+        assert_eq!(
+            error("{date} {summary"),
+            "invalid template: unclosed '{' in '{date} {summary'"
+        );
+        assert_eq!(
+            error("{date} }"),
+            "invalid template: unmatched '}' in '{date} }'"
+        );
+        assert!(error("{subject}")
+            .starts_with("invalid template: unknown field 'subject', expected message"));
+        assert_eq!(
+            error("{summary|upper}"),
+            "invalid template: unknown filter 'upper', expected date, trunc, pad, lpad or basename"
+        );
+        assert_eq!(
+            error("{summary|trunc}"),
+            "invalid template: 'trunc' needs a number, like trunc:10"
+        );
+        assert_eq!(
+            error("{summary|date:%F}"),
+            "invalid template: 'date' only applies to times"
+        );
+        assert_eq!(
+            error("{date:%Q}"),
+            "invalid template: invalid date format '%Q'"
+        );
+    }
+}
@@ -0,0 +1,85 @@
+use git2::build::TreeUpdateBuilder;
+use git2::{FileMode, Oid, Repository, Signature, Time};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::{env, fs, process};
+
+/// A fresh temporary directory, removed when dropped.
+pub struct TempDir(PathBuf);
+
+impl TempDir {
+    pub fn new(name: &str) -> Self {
+        // tests run in parallel, and some make several directories
+        static COUNT: AtomicUsize = AtomicUsize::new(0);
+        let count = COUNT.fetch_add(1, Ordering::Relaxed);
+        let dir = env::temp_dir().join(format!("timetable-{name}-{}-{count}", process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        Self(dir)
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.0);
+    }
+}
+
+/// A repository in a fresh temporary directory, removed when dropped.
+pub struct TestRepo {
+    pub repo: Repository,
+    time: i64,
+    _dir: TempDir,
+}
+
+impl TestRepo {
+    pub fn new(name: &str) -> Self {
+        let dir = TempDir::new(name);
+        let repo = Repository::init(dir.path()).unwrap();
+        Self {
+            repo,
+            time: 1_700_000_000,
+            _dir: dir,
+        }
+    }
+
+    /// Makes a commit with the tree of its first parent, an hour after the
+    /// previous one.
+    pub fn commit(&mut self, message: &str, parents: &[Oid]) -> Oid {
+        self.commit_files(message, parents, &[])
+    }
+
+    /// Makes a commit that writes `files`, given as path and content, over
+    /// the tree of its first parent, an hour after the previous one.
+    pub fn commit_files(&mut self, message: &str, parents: &[Oid], files: &[(&str, &str)]) -> Oid {
+        self.time += 60 * 60;
+        let time = Time::new(self.time, 0);
+        let signature = Signature::new("Alice", "alice@example.com", &time).unwrap();
+        let parents = parents
+            .iter()
+            .map(|oid| self.repo.find_commit(*oid).unwrap())
+            .collect::<Vec<_>>();
+        let base = match parents.first() {
+            Some(parent) => parent.tree().unwrap(),
+            None => {
+                let empty = self.repo.treebuilder(None).unwrap().write().unwrap();
+                self.repo.find_tree(empty).unwrap()
+            }
+        };
+        let mut update = TreeUpdateBuilder::new();
+        for (path, content) in files {
+            let blob = self.repo.blob(content.as_bytes()).unwrap();
+            update.upsert(*path, blob, FileMode::Blob);
+        }
+        let tree = update.create_updated(&self.repo, &base).unwrap();
+        let tree = self.repo.find_tree(tree).unwrap();
+        let parents = parents.iter().collect::<Vec<_>>();
+        self.repo
+            .commit(None, &signature, &signature, message, &tree, &parents)
+            .unwrap()
+    }
+}
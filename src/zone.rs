@@ -0,0 +1,143 @@
+use chrono::{
+    DateTime, Duration, FixedOffset, Local, NaiveDateTime, Offset, TimeZone, Timelike, Utc,
+};
+use std::str::FromStr;
+
+/// The time zone that times are displayed in and days are counted in.
+#[derive(Clone, Debug)]
+pub enum Zone {
+    /// The local time zone of this machine.
+    Local,
+    /// The time zone each commit was made in, as recorded in the commit.
+    Commit,
+    Utc,
+    /// A zone from the IANA database, like `Europe/Berlin`.
+    Named(chrono_tz::Tz),
+}
+
+impl Zone {
+    /// The time at `seconds` since the epoch, for a commit made at UTC offset
+    /// `offset_minutes`.
+    pub fn localize(&self, seconds: i64, offset_minutes: i32) -> DateTime<FixedOffset> {
+        let utc = DateTime::<Utc>::from_timestamp(seconds, 0).unwrap_or_default();
+        match self {
+            Zone::Local => utc.with_timezone(&Local).fixed_offset(),
+            Zone::Commit => {
+                let offset = FixedOffset::east_opt(offset_minutes * 60).unwrap_or(Utc.fix());
+                utc.with_timezone(&offset)
+            }
+            Zone::Utc => utc.fixed_offset(),
+            Zone::Named(tz) => utc.with_timezone(tz).fixed_offset(),
+        }
+    }
+
+    /// The seconds since the epoch at which the wall clock shows `local`.
+    ///
+    /// Commits carry their own zone, but a date on the command line doesn't, so
+    /// `Commit` reads it in the local zone. A time that occurs twice when the
+    /// clocks go back means the first occurrence. A time skipped when the
+    /// clocks go forward means the moment the clocks were changed.
+    pub fn timestamp(&self, local: NaiveDateTime) -> i64 {
+        match self {
+            Zone::Local | Zone::Commit => first_occurrence(&Local, local),
+            Zone::Utc => local.and_utc().timestamp(),
+            Zone::Named(tz) => first_occurrence(tz, local),
+        }
+    }
+
+    /// What the wall clock shows at `seconds` since the epoch, the reverse of
+    /// [Zone::timestamp].
+    pub fn wall_clock(&self, seconds: i64) -> NaiveDateTime {
+        match self {
+            Zone::Commit => Zone::Local.localize(seconds, 0).naive_local(),
+            zone => zone.localize(seconds, 0).naive_local(),
+        }
+    }
+}
+
+fn first_occurrence<Tz: TimeZone>(tz: &Tz, local: NaiveDateTime) -> i64 {
+    let mut local = local;
+    loop {
+        if let Some(time) = tz.from_local_datetime(&local).earliest() {
+            return time.timestamp();
+        }
+        // skipped when the clocks went forward, the first minute after the gap is
+        // when they were changed
+        local = local.with_second(0).unwrap_or(local) + Duration::minutes(1);
+    }
+}
+
+impl FromStr for Zone {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "local" => Ok(Zone::Local),
+            "commit" => Ok(Zone::Commit),
+            "utc" | "UTC" => Ok(Zone::Utc),
+            name => name.parse().map(Zone::Named).map_err(|_| {
+                format!("unknown time zone '{name}', expected local, commit, utc or an IANA name like Europe/Berlin")
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn local(time: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M").unwrap()
+    }
+
+    fn at(time: &str) -> i64 {
+        DateTime::parse_from_rfc3339(time).unwrap().timestamp()
+    }
+
+    #[test]
+    fn timestamp_in_named_zone() {
+        let berlin = Zone::Named(chrono_tz::Europe::Berlin);
+        assert_eq!(
+            berlin.timestamp(local("2026-10-17 09:00")),
+            at("2026-10-17T09:00:00+02:00")
+        );
+        assert_eq!(
+            berlin.timestamp(local("2026-12-17 09:00")),
+            at("2026-12-17T09:00:00+01:00")
+        );
+    }
+
+    #[test]
+    fn skipped_time_is_when_the_clocks_went_forward() {
+        let berlin = Zone::Named(chrono_tz::Europe::Berlin);
+        let change = at("2026-03-29T03:00:00+02:00");
+        assert_eq!(berlin.timestamp(local("2026-03-29 02:00")), change);
+        assert_eq!(berlin.timestamp(local("2026-03-29 02:30")), change);
+        assert_eq!(
+            berlin.timestamp(local("2026-03-29 03:00")),
+            change,
+            "the first time after the gap"
+        );
+    }
+
+    #[test]
+    fn repeated_time_is_the_first_occurrence() {
+        let berlin = Zone::Named(chrono_tz::Europe::Berlin);
+        assert_eq!(
+            berlin.timestamp(local("2026-10-25 02:30")),
+            at("2026-10-25T02:30:00+02:00")
+        );
+        assert_eq!(
+            berlin.timestamp(local("2026-10-25 03:00")),
+            at("2026-10-25T03:00:00+01:00")
+        );
+    }
+
+    #[test]
+    fn wall_clock_reverses_timestamp() {
+        let berlin = Zone::Named(chrono_tz::Europe::Berlin);
+        let time = at("2026-10-25T02:30:00+01:00");
+        assert_eq!(berlin.wall_clock(time), local("2026-10-25 02:30"));
+        assert_eq!(Zone::Utc.wall_clock(time), local("2026-10-25 01:30"));
+    }
+}